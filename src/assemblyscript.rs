@@ -0,0 +1,302 @@
+use crate::{
+    module::ParsedModule, profile::Classifier, Import, ImportKind, Profile, Signature, ValueType,
+};
+use wasmparser::{ExternalKind, TypeRef};
+
+/// The memory manager an AssemblyScript binary was built with, chosen with
+/// `--runtime`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AssemblyScriptRuntime {
+    /// Allocates but never frees memory
+    Stub,
+    /// A garbage collector the host has to run by calling `__collect`
+    Minimal,
+    /// The default garbage collector, running in steps as memory is
+    /// allocated
+    Incremental,
+    /// The minimal or the incremental runtime, which stripped binaries do
+    /// not tell apart
+    Collecting,
+}
+
+impl AssemblyScriptRuntime {
+    pub fn name(self) -> &'static str {
+        match self {
+            AssemblyScriptRuntime::Stub => "stub",
+            AssemblyScriptRuntime::Minimal => "minimal",
+            AssemblyScriptRuntime::Incremental => "incremental",
+            AssemblyScriptRuntime::Collecting => "minimal or incremental",
+        }
+    }
+}
+
+/// The imports an AssemblyScript binary expects from its host, grouped by
+/// what they give access to
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AssemblyScriptAssumptions {
+    /// The runtime variant, if the binary tells which one it uses
+    pub runtime: Option<AssemblyScriptRuntime>,
+    /// Whether the runtime is guessed from the exports, because the name
+    /// section was stripped
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub runtime_guessed: bool,
+    /// Whether the binary exports `__new` and `__pin`, which the loader and
+    /// generated bindings use to pass managed objects
+    pub exports_runtime: bool,
+    pub process: Vec<Import>,
+    pub tracing: Vec<Import>,
+    pub randomness: Vec<Import>,
+    pub time: Vec<Import>,
+    /// Other JS functions the generated bindings provide, such as those
+    /// of `declare` statements
+    pub js_interop: Vec<Import>,
+}
+
+/// The exports AssemblyScript adds with `--exportRuntime`
+const RUNTIME_EXPORTS: &[&str] = &["__new", "__pin", "__unpin", "__collect", "__rtti_base"];
+
+impl AssemblyScriptAssumptions {
+    /// Recognize an AssemblyScript binary by its `abort` import, the
+    /// runtime it exports or the standard library names in its name section
+    pub(crate) fn detect(module: &ParsedModule) -> Option<AssemblyScriptAssumptions> {
+        // AssemblyScript's abort takes the message, file, line and column,
+        // unlike the abort of C
+        let abort = Signature {
+            params: vec![ValueType::I32; 4],
+            results: Vec::new(),
+        };
+        let imports = module.imports.iter().any(|i| match i.ty {
+            TypeRef::Func(index) if i.module == "env" && i.name == "abort" => {
+                module.types.get(index as usize) == Some(&Some(abort.clone()))
+            }
+            _ => false,
+        });
+        let exported = |name: &str| {
+            module
+                .exports
+                .iter()
+                .any(|e| e.name == name && e.kind != ExternalKind::Memory)
+        };
+        let runtime_exports = RUNTIME_EXPORTS.iter().any(|e| exported(e));
+        let library = module.names.iter().any(|(_, n)| n.starts_with("~lib/"));
+        if !imports && !runtime_exports && !library {
+            return None;
+        }
+
+        // Only the name section tells the runtime apart: every variant
+        // exports `__new`, and `__collect` can be left out of any of them
+        let names_runtime = |path: &str| module.names.iter().any(|(_, n)| n.starts_with(path));
+        let runtime = if names_runtime("~lib/rt/itcms/") {
+            Some(AssemblyScriptRuntime::Incremental)
+        } else if names_runtime("~lib/rt/tcms/") {
+            Some(AssemblyScriptRuntime::Minimal)
+        } else if names_runtime("~lib/rt/stub/") {
+            Some(AssemblyScriptRuntime::Stub)
+        } else {
+            None
+        };
+        // Without the name section, the runtime exports of `--exportRuntime`
+        // are all there is to go on. This is only a heuristic: the garbage
+        // collectors export `__collect`, but the stub can export a no-op
+        // one too.
+        let runtime_guessed = runtime.is_none() && exported("__new");
+        let runtime = if !runtime_guessed {
+            runtime
+        } else if exported("__collect") {
+            Some(AssemblyScriptRuntime::Collecting)
+        } else {
+            Some(AssemblyScriptRuntime::Stub)
+        };
+        Some(AssemblyScriptAssumptions {
+            runtime,
+            runtime_guessed,
+            exports_runtime: exported("__new") && exported("__pin"),
+            ..AssemblyScriptAssumptions::default()
+        })
+    }
+}
+
+impl Classifier for AssemblyScriptAssumptions {
+    fn classify(&mut self, import: Import) -> Result<(), Import> {
+        if import.module != "env" || import.kind != ImportKind::Function {
+            return Err(import);
+        }
+        let calls = match import.field.as_str() {
+            "abort" | "process.exit" => &mut self.process,
+            "trace" => &mut self.tracing,
+            "seed" | "Math.random" => &mut self.randomness,
+            "Date.now" | "performance.now" => &mut self.time,
+            field if field.starts_with("console.") => &mut self.tracing,
+            // The bindings name imports after the JS they call
+            field if field.contains('.') => &mut self.js_interop,
+            _ => return Err(import),
+        };
+        calls.push(import);
+        Ok(())
+    }
+}
+
+impl Profile for AssemblyScriptAssumptions {
+    fn id(&self) -> &'static str {
+        "assemblyscript"
+    }
+
+    fn name(&self) -> &'static str {
+        "AssemblyScript"
+    }
+
+    fn categories(&self) -> Vec<(&'static str, &[Import])> {
+        vec![
+            ("process", &self.process),
+            ("tracing", &self.tracing),
+            ("randomness", &self.randomness),
+            ("time", &self.time),
+            ("js_interop", &self.js_interop),
+        ]
+    }
+
+    fn notes(&self) -> Vec<String> {
+        let mut notes = Vec::new();
+        match self.runtime {
+            Some(AssemblyScriptRuntime::Minimal) => notes.push(
+                "The binary uses the minimal runtime, the host has to call __collect to free memory"
+                    .to_owned(),
+            ),
+            Some(runtime) if self.runtime_guessed => notes.push(format!(
+                "The binary likely uses the {} runtime, going by its exports since the name section was stripped",
+                runtime.name()
+            )),
+            Some(runtime) => notes.push(format!("The binary uses the {} runtime", runtime.name())),
+            None => {}
+        }
+        if self.exports_runtime {
+            notes.push(
+                "The binary exports the runtime interface used to pass managed objects".to_owned(),
+            );
+        }
+        if !self.randomness.is_empty() {
+            notes.push("The binary depends on randomness provided by the host".to_owned());
+        }
+        if !self.tracing.is_empty() {
+            notes.push("The binary sends trace output to the host".to_owned());
+        }
+        notes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, fields};
+
+    const ABORT: &str = r#"(import "env" "abort" (func (param i32 i32 i32 i32)))"#;
+
+    #[test]
+    fn classifies_host_imports() {
+        let report = analyze(&format!(
+            r#"(module
+                {}
+                (import "env" "trace" (func))
+                (import "env" "seed" (func (result f64)))
+                (import "env" "Date.now" (func (result f64)))
+                (import "env" "console.log" (func (param i32)))
+                (import "env" "document.title" (func))
+                (import "env" "helper" (func)))"#,
+            ABORT
+        ));
+        let assemblyscript = report.assumptions.assemblyscript.as_ref().unwrap();
+        assert_eq!(fields(&assemblyscript.process), vec!["abort"]);
+        assert_eq!(
+            fields(&assemblyscript.tracing),
+            vec!["trace", "console.log"]
+        );
+        assert_eq!(fields(&assemblyscript.randomness), vec!["seed"]);
+        assert_eq!(fields(&assemblyscript.time), vec!["Date.now"]);
+        assert_eq!(fields(&assemblyscript.js_interop), vec!["document.title"]);
+        assert_eq!(fields(&report.assumptions.unknown), vec!["helper"]);
+        assert_eq!(assemblyscript.runtime, None);
+    }
+
+    #[test]
+    fn tells_the_runtime_from_the_name_section() {
+        for (path, runtime) in [
+            ("itcms", AssemblyScriptRuntime::Incremental),
+            ("tcms", AssemblyScriptRuntime::Minimal),
+            ("stub", AssemblyScriptRuntime::Stub),
+        ] {
+            let report = analyze(&format!(
+                "(module {} (func $~lib/rt/{}/__new))",
+                ABORT, path
+            ));
+            assert_eq!(
+                report.assumptions.assemblyscript.unwrap().runtime,
+                Some(runtime)
+            );
+        }
+    }
+
+    #[test]
+    fn reads_the_exported_runtime() {
+        let report = analyze(
+            r#"(module
+                (func (export "__new"))
+                (func (export "__pin")))"#,
+        );
+        let assemblyscript = report.assumptions.assemblyscript.unwrap();
+        assert_eq!(assemblyscript.runtime, Some(AssemblyScriptRuntime::Stub));
+        assert!(assemblyscript.runtime_guessed);
+        assert!(assemblyscript.exports_runtime);
+        assert_eq!(
+            assemblyscript.notes(),
+            vec![
+                "The binary likely uses the stub runtime, going by its exports since the name section was stripped",
+                "The binary exports the runtime interface used to pass managed objects",
+            ]
+        );
+    }
+
+    #[test]
+    fn guesses_the_runtime_of_stripped_binaries() {
+        let report = analyze(
+            r#"(module
+                (func (export "__new"))
+                (func (export "__pin"))
+                (func (export "__collect")))"#,
+        );
+        let assemblyscript = report.assumptions.assemblyscript.unwrap();
+        assert_eq!(
+            assemblyscript.runtime,
+            Some(AssemblyScriptRuntime::Collecting)
+        );
+        assert!(assemblyscript.runtime_guessed);
+
+        // The name section wins over the exports
+        let report = analyze(
+            r#"(module
+                (func $~lib/rt/tcms/__new (export "__new"))
+                (func (export "__collect")))"#,
+        );
+        let assemblyscript = report.assumptions.assemblyscript.unwrap();
+        assert_eq!(assemblyscript.runtime, Some(AssemblyScriptRuntime::Minimal));
+        assert!(!assemblyscript.runtime_guessed);
+    }
+
+    #[test]
+    fn the_abort_of_c_is_not_assemblyscript() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "env" "abort" (func))
+                (import "env" "Date.now" (func (result f64))))"#,
+        );
+        assert!(report.assumptions.assemblyscript.is_none());
+        assert_eq!(
+            fields(&report.assumptions.unknown),
+            vec!["abort", "Date.now"]
+        );
+    }
+}
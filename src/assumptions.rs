@@ -0,0 +1,446 @@
+use crate::{
+    module::ParsedModule, profile, profile::Classifier, AssemblyScriptAssumptions,
+    ComponentAssumptions, CosmWasmAssumptions, EmscriptenAssumptions, ExtismAssumptions,
+    FastlyAssumptions, GoAssumptions, Ic0Assumptions, NearAssumptions, Profile,
+    ProxyWasmAssumptions, Reachability, SealAssumptions, StylusAssumptions, WasiAssumptions,
+    WasiSnapshot, WasmBindgenAssumptions,
+};
+use std::fmt;
+use wasmparser::{RefType, TypeRef, ValType};
+
+/// What kind of item an import brings into the binary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ImportKind {
+    Function,
+    Table,
+    Memory,
+    Global,
+    /// An exception tag
+    Tag,
+}
+
+impl ImportKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ImportKind::Function => "function",
+            ImportKind::Table => "table",
+            ImportKind::Memory => "memory",
+            ImportKind::Global => "global",
+            ImportKind::Tag => "tag",
+        }
+    }
+}
+
+/// A value type as it appears in a function signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ValueType {
+    I32,
+    I64,
+    F32,
+    F64,
+    V128,
+    FuncRef,
+    ExternRef,
+    /// Any other reference type, such as the ones of the GC proposal
+    Ref,
+}
+
+impl From<ValType> for ValueType {
+    fn from(value_type: ValType) -> ValueType {
+        match value_type {
+            ValType::I32 => ValueType::I32,
+            ValType::I64 => ValueType::I64,
+            ValType::F32 => ValueType::F32,
+            ValType::F64 => ValueType::F64,
+            ValType::V128 => ValueType::V128,
+            ValType::Ref(RefType::FUNCREF) => ValueType::FuncRef,
+            ValType::Ref(RefType::EXTERNREF) => ValueType::ExternRef,
+            ValType::Ref(_) => ValueType::Ref,
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ValueType::I32 => "i32",
+            ValueType::I64 => "i64",
+            ValueType::F32 => "f32",
+            ValueType::F64 => "f64",
+            ValueType::V128 => "v128",
+            ValueType::FuncRef => "funcref",
+            ValueType::ExternRef => "externref",
+            ValueType::Ref => "ref",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The type of an imported function
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Signature {
+    pub params: Vec<ValueType>,
+    pub results: Vec<ValueType>,
+}
+
+impl fmt::Display for Signature {
+    /// Formats the signature like `(i32, i64) -> i32`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |types: &[ValueType]| {
+            types
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        write!(f, "({}) -> ", list(&self.params))?;
+        match self.results.as_slice() {
+            [result] => write!(f, "{}", result),
+            results => write!(f, "({})", list(results)),
+        }
+    }
+}
+
+impl From<&wasmparser::FuncType> for Signature {
+    fn from(function_type: &wasmparser::FuncType) -> Signature {
+        Signature {
+            params: function_type.params().iter().map(|&p| p.into()).collect(),
+            results: function_type.results().iter().map(|&r| r.into()).collect(),
+        }
+    }
+}
+
+/// A single entry of the import section
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Import {
+    pub module: String,
+    pub field: String,
+    pub kind: ImportKind,
+    /// The type of the import if it is a function
+    pub signature: Option<Signature>,
+    /// Whether any code path calls the import, if it is a function and the
+    /// analyzer walked the call graph
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reachability: Option<Reachability>,
+}
+
+impl Import {
+    pub(crate) fn new(module: &ParsedModule, import: &wasmparser::Import) -> Import {
+        let (kind, signature) = match import.ty {
+            TypeRef::Func(index) | TypeRef::FuncExact(index) => {
+                let signature = module.types.get(index as usize).cloned().flatten();
+                (ImportKind::Function, signature)
+            }
+            TypeRef::Table(_) => (ImportKind::Table, None),
+            TypeRef::Memory(_) => (ImportKind::Memory, None),
+            TypeRef::Global(_) => (ImportKind::Global, None),
+            TypeRef::Tag(_) => (ImportKind::Tag, None),
+        };
+        Import {
+            module: import.module.to_owned(),
+            field: import.name.to_owned(),
+            kind,
+            signature,
+            reachability: None,
+        }
+    }
+}
+
+/// The size limits of an imported memory, in pages, or table, in elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Limits {
+    pub initial: u64,
+    pub maximum: Option<u64>,
+}
+
+/// A linear memory the binary expects the host to provide
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryImport {
+    pub import: Import,
+    pub limits: Limits,
+    /// Whether the memory is shared between threads
+    pub shared: bool,
+    /// Whether the memory is indexed with 64 bit addresses
+    pub memory64: bool,
+}
+
+impl MemoryImport {
+    pub(crate) fn new(import: Import, ty: &wasmparser::MemoryType) -> MemoryImport {
+        MemoryImport {
+            import,
+            limits: Limits {
+                initial: ty.initial,
+                maximum: ty.maximum,
+            },
+            shared: ty.shared,
+            memory64: ty.memory64,
+        }
+    }
+}
+
+/// A table the binary expects the host to provide
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TableImport {
+    pub import: Import,
+    pub element_type: ValueType,
+    pub limits: Limits,
+}
+
+impl TableImport {
+    pub(crate) fn new(import: Import, ty: &wasmparser::TableType) -> TableImport {
+        TableImport {
+            import,
+            element_type: ValType::Ref(ty.element_type).into(),
+            limits: Limits {
+                initial: ty.initial,
+                maximum: ty.maximum,
+            },
+        }
+    }
+}
+
+/// The imports of a binary, grouped by the environment expected to
+/// provide them
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Assumptions {
+    pub wasi: WasiAssumptions,
+    /// The `env` imports of a binary built with Emscripten
+    pub emscripten: Option<EmscriptenAssumptions>,
+    /// The JS glue imports of a binary built with wasm-bindgen
+    pub wasm_bindgen: Option<WasmBindgenAssumptions>,
+    /// The runtime imports of a binary built with Go or TinyGo
+    pub go: Option<GoAssumptions>,
+    /// The host imports of a binary built with AssemblyScript
+    pub assemblyscript: Option<AssemblyScriptAssumptions>,
+    /// The host calls of a proxy-wasm filter
+    pub proxy_wasm: Option<ProxyWasmAssumptions>,
+    /// The host calls of a Fastly Compute service
+    pub fastly: Option<FastlyAssumptions>,
+    /// The host functions of an Extism plugin
+    pub extism: Option<ExtismAssumptions>,
+    /// Set for CosmWasm contracts, or for any binary checked with a chosen interface version
+    pub cosmwasm: Option<CosmWasmAssumptions>,
+    /// Set for NEAR contracts
+    pub near: Option<NearAssumptions>,
+    /// Set for Internet Computer canisters
+    pub ic0: Option<Ic0Assumptions>,
+    /// Set for ink! contracts and other contracts of pallet-contracts
+    pub seal: Option<SealAssumptions>,
+    /// Set for Arbitrum Stylus programs
+    pub stylus: Option<StylusAssumptions>,
+    /// The imports and exports of the world of a component, set when the
+    /// binary is a component rather than a core module
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub component: Option<ComponentAssumptions>,
+    pub memories: Vec<MemoryImport>,
+    pub tables: Vec<TableImport>,
+    pub unknown: Vec<Import>,
+}
+
+impl Assumptions {
+    pub(crate) fn new() -> Assumptions {
+        Assumptions {
+            wasi: WasiAssumptions::new(),
+            emscripten: None,
+            wasm_bindgen: None,
+            go: None,
+            assemblyscript: None,
+            proxy_wasm: None,
+            fastly: None,
+            extism: None,
+            cosmwasm: None,
+            near: None,
+            ic0: None,
+            seal: None,
+            stylus: None,
+            component: None,
+            memories: Vec::new(),
+            tables: Vec::new(),
+            unknown: Vec::new(),
+        }
+    }
+
+    pub(crate) fn add_wasi(&mut self, snapshot: WasiSnapshot, import: Import) {
+        self.wasi.add(snapshot, import)
+    }
+
+    pub(crate) fn add_memory(&mut self, memory: MemoryImport) {
+        self.memories.push(memory)
+    }
+
+    pub(crate) fn add_table(&mut self, table: TableImport) {
+        self.tables.push(table)
+    }
+
+    /// Hand the import to the profiles the binary matches, and keep it as
+    /// unknown if none of them recognizes it
+    pub(crate) fn add_unknown(&mut self, mut import: Import) {
+        for classifier in self.classifiers() {
+            match classifier.classify(import) {
+                Ok(()) => return,
+                Err(unclaimed) => import = unclaimed,
+            }
+        }
+        self.unknown.push(import)
+    }
+
+    /// The toolchains and hosts the binary was found to be built for
+    pub fn profiles(&self) -> Vec<&dyn Profile> {
+        let mut profiles: Vec<&dyn Profile> = Vec::new();
+        if let Some(emscripten) = &self.emscripten {
+            profiles.push(emscripten);
+        }
+        if let Some(wasm_bindgen) = &self.wasm_bindgen {
+            profiles.push(wasm_bindgen);
+        }
+        if let Some(go) = &self.go {
+            profiles.push(go);
+        }
+        if let Some(assemblyscript) = &self.assemblyscript {
+            profiles.push(assemblyscript);
+        }
+        if let Some(proxy_wasm) = &self.proxy_wasm {
+            profiles.push(proxy_wasm);
+        }
+        if let Some(fastly) = &self.fastly {
+            profiles.push(fastly);
+        }
+        if let Some(extism) = &self.extism {
+            profiles.push(extism);
+        }
+        if let Some(cosmwasm) = &self.cosmwasm {
+            profiles.push(cosmwasm);
+        }
+        if let Some(near) = &self.near {
+            profiles.push(near);
+        }
+        if let Some(ic0) = &self.ic0 {
+            profiles.push(ic0);
+        }
+        if let Some(seal) = &self.seal {
+            profiles.push(seal);
+        }
+        if let Some(stylus) = &self.stylus {
+            profiles.push(stylus);
+        }
+        profiles
+    }
+
+    fn classifiers(&mut self) -> Vec<&mut dyn Classifier> {
+        let mut classifiers: Vec<&mut dyn Classifier> = Vec::new();
+        if let Some(emscripten) = &mut self.emscripten {
+            classifiers.push(emscripten);
+        }
+        if let Some(wasm_bindgen) = &mut self.wasm_bindgen {
+            classifiers.push(wasm_bindgen);
+        }
+        if let Some(go) = &mut self.go {
+            classifiers.push(go);
+        }
+        if let Some(assemblyscript) = &mut self.assemblyscript {
+            classifiers.push(assemblyscript);
+        }
+        if let Some(proxy_wasm) = &mut self.proxy_wasm {
+            classifiers.push(proxy_wasm);
+        }
+        if let Some(fastly) = &mut self.fastly {
+            classifiers.push(fastly);
+        }
+        if let Some(extism) = &mut self.extism {
+            classifiers.push(extism);
+        }
+        if let Some(cosmwasm) = &mut self.cosmwasm {
+            classifiers.push(cosmwasm);
+        }
+        if let Some(near) = &mut self.near {
+            classifiers.push(near);
+        }
+        if let Some(ic0) = &mut self.ic0 {
+            classifiers.push(ic0);
+        }
+        if let Some(seal) = &mut self.seal {
+            classifiers.push(seal);
+        }
+        if let Some(stylus) = &mut self.stylus {
+            classifiers.push(stylus);
+        }
+        classifiers
+    }
+
+    /// Every import along with the name of the category it was sorted into.
+    /// Profiles share the WASI categories, while their other categories are
+    /// prefixed with the profile, like `near.storage`, so categories of
+    /// different profiles never mix. Imported memories and tables are in the
+    /// `memory` and `table` categories, and imports kontrolleur does not know
+    /// about are in the `unknown` category.
+    pub fn categorized(&self) -> Vec<(String, &Import)> {
+        let known = self.wasi.categories();
+        let profiles = self.profiles();
+        let mut categorized = Vec::new();
+        for &(category, imports) in &known {
+            categorized.extend(imports.iter().map(|i| (category.to_owned(), i)));
+        }
+        for profile in &profiles {
+            for (category, imports) in profile.categories() {
+                let category = if known.iter().any(|&(c, _)| c == category) {
+                    category.to_owned()
+                } else {
+                    let category = format!("{}.{}", profile.id(), category);
+                    debug_assert!(
+                        profile::is_category(&category),
+                        "{} is missing from profile::CATEGORIES",
+                        category
+                    );
+                    category
+                };
+                categorized.extend(imports.iter().map(|i| (category.clone(), i)));
+            }
+        }
+        let memories = self.memories.iter().map(|m| &m.import);
+        categorized.extend(memories.map(|i| ("memory".to_owned(), i)));
+        let tables = self.tables.iter().map(|t| &t.import);
+        categorized.extend(tables.map(|i| ("table".to_owned(), i)));
+        let unknown = profiles.iter().flat_map(|p| p.unknown());
+        let unknown = self.wasi.unknown.iter().chain(unknown).chain(&self.unknown);
+        categorized.extend(unknown.map(|i| ("unknown".to_owned(), i)));
+        categorized
+    }
+
+    /// Whether a WASI import of the binary, or of a core module of a
+    /// component, does not match the definition of the call in its
+    /// snapshot, which keeps the binary from loading
+    pub fn has_signature_mismatches(&self) -> bool {
+        !self.wasi.signature_mismatches.is_empty()
+            || self
+                .component
+                .iter()
+                .flat_map(|c| &c.core_modules)
+                .any(|m| m.report.assumptions.has_signature_mismatches())
+    }
+
+    /// Whether the binary, or a core module of a component, breaks the
+    /// rules of a profile
+    pub fn has_profile_violations(&self) -> bool {
+        self.profiles().iter().any(|p| !p.errors().is_empty())
+            || self
+                .component
+                .iter()
+                .flat_map(|c| &c.core_modules)
+                .any(|m| m.report.assumptions.has_profile_violations())
+    }
+
+    pub fn count(&self) -> usize {
+        let profiled: usize = self.profiles().iter().map(|p| p.count()).sum();
+        self.unknown.len() + self.memories.len() + self.tables.len() + self.wasi.count() + profiled
+    }
+}
@@ -0,0 +1,214 @@
+use crate::module::ParsedModule;
+use wasmparser::{ExternalKind, Operator, TypeRef};
+
+/// Whether any code path can call an imported function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Reachability {
+    /// Called through a chain of direct calls from an export or the start
+    /// function
+    Reachable,
+    /// Only called from functions that are themselves only reachable
+    /// through `call_indirect`
+    IndirectOnly,
+    /// Never called by any code path
+    Unreachable,
+}
+
+impl Reachability {
+    pub fn name(self) -> &'static str {
+        match self {
+            Reachability::Reachable => "reachable",
+            Reachability::IndirectOnly => "indirect_only",
+            Reachability::Unreachable => "unreachable",
+        }
+    }
+
+    /// Whether the function can be called at all
+    pub fn is_exercised(self) -> bool {
+        self != Reachability::Unreachable
+    }
+}
+
+/// The direct calls between the functions of a module. Functions are
+/// identified by their index in the function index space, so imported
+/// functions come first.
+pub(crate) struct CallGraph {
+    pub(crate) imported: u32,
+    /// The functions each function calls directly, in the order of the calls
+    pub(crate) callees: Vec<Vec<u32>>,
+    /// Whether each function contains a `call_indirect`
+    calls_indirect: Vec<bool>,
+    /// The functions placed in tables by element segments
+    table_members: Vec<u32>,
+    /// Whether the host can get at the tables, and so call their members
+    tables_shared: bool,
+    /// The exported functions and the start function, with the name they
+    /// are entered by
+    pub(crate) roots: Vec<(String, u32)>,
+}
+
+impl CallGraph {
+    pub(crate) fn new(module: &ParsedModule) -> CallGraph {
+        let imported = module.imported_functions;
+        let mut callees = vec![Vec::new(); imported as usize];
+        let mut calls_indirect = vec![false; imported as usize];
+        let mut table_members = module.table_members.clone();
+        for body in &module.bodies {
+            let mut calls = Vec::new();
+            let mut indirect = false;
+            // A body that fails to decode still contributes the calls read
+            // before the error
+            if let Ok(mut operators) = body.get_operators_reader() {
+                while let Ok(operator) = operators.read() {
+                    match operator {
+                        Operator::Call { function_index }
+                        | Operator::ReturnCall { function_index } => calls.push(function_index),
+                        Operator::CallIndirect { .. }
+                        | Operator::ReturnCallIndirect { .. }
+                        | Operator::CallRef { .. }
+                        | Operator::ReturnCallRef { .. } => indirect = true,
+                        // Functions referenced by `ref.func` can be called
+                        // just like the ones in tables
+                        Operator::RefFunc { function_index } => table_members.push(function_index),
+                        _ => {}
+                    }
+                }
+            }
+            callees.push(calls);
+            calls_indirect.push(indirect);
+        }
+
+        let tables_shared = module
+            .imports
+            .iter()
+            .any(|i| matches!(i.ty, TypeRef::Table(_)))
+            || module.exports.iter().any(|e| e.kind == ExternalKind::Table);
+
+        let mut roots: Vec<_> = module
+            .exports
+            .iter()
+            .filter(|e| e.kind == ExternalKind::Func)
+            .map(|e| (e.name.to_owned(), e.index))
+            .collect();
+        if let Some(start) = module.start {
+            roots.push(("start function".to_owned(), start));
+        }
+
+        CallGraph {
+            imported,
+            callees,
+            calls_indirect,
+            table_members,
+            tables_shared,
+            roots,
+        }
+    }
+
+    /// The reachability of every imported function, by function index
+    pub(crate) fn import_reachability(&self) -> Vec<Reachability> {
+        let roots: Vec<_> = self.roots.iter().map(|(_, index)| *index).collect();
+        self.import_reachability_from(&roots)
+    }
+
+    /// The reachability of every imported function when the module is only
+    /// entered through `roots`, by function index
+    pub(crate) fn import_reachability_from(&self, roots: &[u32]) -> Vec<Reachability> {
+        let direct = self.reachable_from(roots);
+        let indirect_possible = self.tables_shared
+            || direct
+                .iter()
+                .zip(&self.calls_indirect)
+                .any(|(&reachable, &indirect)| reachable && indirect);
+        let indirect = if indirect_possible {
+            self.reachable_from(&self.table_members)
+        } else {
+            vec![false; direct.len()]
+        };
+        (0..self.imported as usize)
+            .map(|i| {
+                if direct[i] {
+                    Reachability::Reachable
+                } else if indirect[i] {
+                    Reachability::IndirectOnly
+                } else {
+                    Reachability::Unreachable
+                }
+            })
+            .collect()
+    }
+
+    /// Which functions can be reached from `roots` through direct calls
+    pub(crate) fn reachable_from(&self, roots: &[u32]) -> Vec<bool> {
+        let mut reached = vec![false; self.callees.len()];
+        let mut stack: Vec<u32> = roots.to_vec();
+        while let Some(index) = stack.pop() {
+            match reached.get_mut(index as usize) {
+                Some(reached) if !*reached => *reached = true,
+                _ => continue,
+            }
+            stack.extend(&self.callees[index as usize]);
+        }
+        reached
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, tests::wasm};
+    use Reachability::*;
+
+    fn reachability(wat: &str) -> Vec<Reachability> {
+        CallGraph::new(&parse::parse(&wasm(wat)).unwrap()).import_reachability()
+    }
+
+    #[test]
+    fn follows_direct_calls_from_exports_and_start() {
+        let imports = reachability(
+            r#"(module
+                (import "host" "a" (func $a))
+                (import "host" "b" (func $b))
+                (import "host" "c" (func $c))
+                (func $helper call $a call $helper)
+                (func (export "run") call $helper)
+                (func $init call $b)
+                (func call $c)
+                (start $init))"#,
+        );
+        assert_eq!(imports, vec![Reachable, Reachable, Unreachable]);
+    }
+
+    #[test]
+    fn table_members_need_a_reachable_call_indirect() {
+        let module = |entry: &str| {
+            format!(
+                r#"(module
+                    (import "host" "a" (func $a))
+                    (type $t (func))
+                    (table 1 funcref)
+                    (elem (i32.const 0) $indirect)
+                    (func $indirect call $a)
+                    (func $dispatch i32.const 0 call_indirect (type $t))
+                    (func $plain)
+                    (func (export "run") call {}))"#,
+                entry
+            )
+        };
+        assert_eq!(reachability(&module("$dispatch")), vec![IndirectOnly]);
+        assert_eq!(reachability(&module("$plain")), vec![Unreachable]);
+    }
+
+    #[test]
+    fn shared_tables_can_be_called_by_the_host() {
+        let imports = reachability(
+            r#"(module
+                (import "host" "a" (func $a))
+                (table (export "table") 1 funcref)
+                (elem (i32.const 0) $indirect)
+                (func $indirect call $a))"#,
+        );
+        assert_eq!(imports, vec![IndirectOnly]);
+    }
+}
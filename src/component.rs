@@ -0,0 +1,1355 @@
+use crate::{
+    callgraph::CallGraph, module::ParsedModule, parse, wasi, Import, ImportKind, KontrolleurError,
+    Report, SectionId, WasiSnapshot,
+};
+use wasmparser::{
+    CanonicalFunction, ComponentAlias, ComponentExternalKind, ComponentInstance, ComponentName,
+    ComponentOuterAliasKind, ComponentType, ComponentTypeRef, ExternalKind, Instance,
+    InstanceTypeDeclaration, KnownCustom, Operator, Parser, Payload, TypeRef,
+};
+
+/// The module name given to functions a component imports on their own
+/// rather than as part of an interface
+pub(crate) const ROOT: &str = "$root";
+
+/// How many aliases, instances and tables are followed to find where an
+/// import comes from before giving up
+const MAX_DEPTH: usize = 32;
+
+/// What kind of item a component imports or exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ComponentItemKind {
+    /// A core module
+    Module,
+    Function,
+    Value,
+    Type,
+    /// An instance, which is how interfaces like `wasi:cli/environment` are
+    /// imported and exported
+    Instance,
+    Component,
+}
+
+impl ComponentItemKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ComponentItemKind::Module => "module",
+            ComponentItemKind::Function => "function",
+            ComponentItemKind::Value => "value",
+            ComponentItemKind::Type => "type",
+            ComponentItemKind::Instance => "instance",
+            ComponentItemKind::Component => "component",
+        }
+    }
+}
+
+impl From<ComponentExternalKind> for ComponentItemKind {
+    fn from(kind: ComponentExternalKind) -> ComponentItemKind {
+        match kind {
+            ComponentExternalKind::Module => ComponentItemKind::Module,
+            ComponentExternalKind::Func => ComponentItemKind::Function,
+            ComponentExternalKind::Value => ComponentItemKind::Value,
+            ComponentExternalKind::Type => ComponentItemKind::Type,
+            ComponentExternalKind::Instance => ComponentItemKind::Instance,
+            ComponentExternalKind::Component => ComponentItemKind::Component,
+        }
+    }
+}
+
+/// An import or export of a component
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComponentItem {
+    /// The name of the item, such as `wasi:filesystem/types@0.2.0` for an
+    /// interface
+    pub name: String,
+    pub kind: ComponentItemKind,
+    /// The category the functions of an imported interface or an imported
+    /// function were sorted into
+    pub category: Option<String>,
+    /// The functions of an instance, as far as the component declares them
+    pub functions: Vec<String>,
+}
+
+/// What a component imports from its host and exports to it, which is the
+/// world it targets
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComponentAssumptions {
+    pub imports: Vec<ComponentItem>,
+    /// The exports of the world
+    pub exports: Vec<ComponentItem>,
+    /// The number of core modules embedded directly in the component
+    pub modules: usize,
+    /// The number of components nested in the component
+    pub components: usize,
+    /// Every core module in the component, nested components included, in
+    /// the order they appear in the binary
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub core_modules: Vec<CoreModule>,
+}
+
+/// A core module embedded in a component, analyzed like a module of its own
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoreModule {
+    /// The position of the module among the core modules of the binary
+    pub index: u32,
+    /// The name the component gives the module, like `main` or
+    /// `wit-component:adapter:wasi_snapshot_preview1`
+    pub name: Option<String>,
+    /// The byte offset of the module in the component
+    pub offset: usize,
+    /// Whether the module adapts the calls of an older WASI snapshot to the
+    /// interfaces of the component
+    pub adapter: bool,
+    /// The analysis of the module on its own. Byte offsets are the ones in
+    /// the component.
+    pub report: Report,
+    /// Where each import of the module comes from, in the order of the
+    /// import section. Empty if the component never instantiates the
+    /// module.
+    pub links: Vec<ImportLink>,
+}
+
+/// How the component satisfies an import of one of its core modules, when
+/// the module is first instantiated
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImportLink {
+    pub module: String,
+    pub field: String,
+    pub source: ImportSource,
+    /// The functions of component imports the providing export can end up
+    /// calling, following direct calls through the modules, for imports
+    /// satisfied by another module
+    pub reaches: Vec<String>,
+}
+
+/// What an import of a core module is satisfied with
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ImportSource {
+    /// An export of another core module, by its index
+    Module { module: u32, export: String },
+    /// An export of the module adapting an older WASI snapshot, by its
+    /// index
+    Adapter { module: u32, export: String },
+    /// A function of an import of the component the module is in, lowered
+    /// with `canon lower`. `function` is `None` for functions the component
+    /// imports on their own.
+    ComponentImport {
+        import: String,
+        function: Option<String>,
+    },
+    /// A function the component model provides, like `resource.drop`
+    Builtin { name: String },
+    /// Something kontrolleur could not trace back, such as an item of an
+    /// instance of a nested component
+    Unresolved,
+}
+
+/// A component whose core modules are parsed but not analyzed yet
+pub(crate) struct ParsedComponent<'a> {
+    pub(crate) assumptions: ComponentAssumptions,
+    pub(crate) modules: Vec<NestedModule<'a>>,
+}
+
+/// A core module of a component, along with where its imports come from
+pub(crate) struct NestedModule<'a> {
+    pub(crate) name: Option<String>,
+    pub(crate) offset: usize,
+    pub(crate) bytes: &'a [u8],
+    pub(crate) module: ParsedModule<'a>,
+    pub(crate) adapter: bool,
+    pub(crate) links: Vec<ImportLink>,
+}
+
+/// A core instance, created by instantiating a module or by bundling items
+enum CoreInstance {
+    Instantiate {
+        module: u32,
+        args: Vec<(String, u32)>,
+    },
+    Exports(Vec<(String, ExternalKind, u32)>),
+}
+
+/// Where a core function, table, memory or global of a component comes
+/// from
+enum CoreItem {
+    Export { instance: u32, name: String },
+    Lowered(u32),
+    Builtin(&'static str),
+}
+
+/// Where a function of a component comes from
+#[derive(Clone)]
+enum Function {
+    Import(String),
+    InstanceExport { instance: u32, name: String },
+    Unknown,
+}
+
+/// An instance of a component
+#[derive(Clone, Default)]
+struct ComponentInstanceItem {
+    /// The name of the import, if the instance is imported
+    import: Option<String>,
+    /// The functions of the instance, where they are known
+    functions: Vec<String>,
+    /// The indices of the functions a bundled instance exports
+    exports: Vec<(String, u32)>,
+}
+
+/// The index spaces of a component, nested components having their own
+#[derive(Default)]
+struct Scope {
+    /// The position of every core module among the modules of the binary,
+    /// `None` for modules that are imported or aliased
+    core_modules: Vec<Option<usize>>,
+    core_instances: Vec<CoreInstance>,
+    core_functions: Vec<CoreItem>,
+    core_tables: Vec<CoreItem>,
+    core_memories: Vec<CoreItem>,
+    core_globals: Vec<CoreItem>,
+    core_tags: Vec<CoreItem>,
+    functions: Vec<Function>,
+    instances: Vec<ComponentInstanceItem>,
+    /// The exported functions of every type that is an instance type
+    types: Vec<Option<Vec<String>>>,
+    /// The names of the core modules, from the `component-name` section
+    module_names: Vec<(u32, String)>,
+}
+
+/// Read the imports and exports of the component and the core modules in
+/// it, tracing each import of a module back to the item it is satisfied
+/// with
+pub(crate) fn parse(bytes: &[u8]) -> Result<ParsedComponent<'_>, KontrolleurError> {
+    let malformed = |e| parse::malformed(bytes, e);
+    let mut component = ComponentAssumptions::default();
+    let mut modules: Vec<NestedModule> = Vec::new();
+    let mut scopes = vec![Scope::default()];
+    let mut in_module = false;
+    for payload in Parser::new(0).parse_all(bytes) {
+        let payload = payload.map_err(malformed)?;
+        // The sections of nested modules are parsed on their own
+        if in_module {
+            in_module = !matches!(payload, Payload::End(_));
+            continue;
+        }
+        let top = scopes.len() == 1;
+        match payload {
+            Payload::ComponentSection { .. } => {
+                if top {
+                    component.components += 1;
+                }
+                scopes.push(Scope::default());
+                continue;
+            }
+            Payload::End(_) => {
+                if let Some(scope) = scopes.pop() {
+                    scope.link(&mut modules);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        let scope = match scopes.last_mut() {
+            Some(scope) => scope,
+            None => break,
+        };
+        match payload {
+            Payload::ModuleSection {
+                unchecked_range: range,
+                ..
+            } => {
+                let module_bytes =
+                    bytes
+                        .get(range.clone())
+                        .ok_or_else(|| KontrolleurError::MalformedSection {
+                            section: SectionId::Component,
+                            offset: range.start,
+                            message: "the module does not fit in the component".to_owned(),
+                        })?;
+                let module = ParsedModule::parse(module_bytes)
+                    .map_err(|e| parse::nested(parse::malformed(module_bytes, e), range.start))?;
+                scope.core_modules.push(Some(modules.len()));
+                modules.push(NestedModule {
+                    name: None,
+                    offset: range.start,
+                    bytes: module_bytes,
+                    module,
+                    adapter: false,
+                    links: Vec::new(),
+                });
+                if top {
+                    component.modules += 1;
+                }
+                in_module = true;
+            }
+            Payload::InstanceSection(reader) => {
+                for instance in reader {
+                    scope
+                        .core_instances
+                        .push(match instance.map_err(malformed)? {
+                            Instance::Instantiate { module_index, args } => {
+                                CoreInstance::Instantiate {
+                                    module: module_index,
+                                    args: args
+                                        .iter()
+                                        .map(|arg| (arg.name.to_owned(), arg.index))
+                                        .collect(),
+                                }
+                            }
+                            Instance::FromExports(exports) => CoreInstance::Exports(
+                                exports
+                                    .iter()
+                                    .map(|e| (e.name.to_owned(), core_kind(e.kind), e.index))
+                                    .collect(),
+                            ),
+                        });
+                }
+            }
+            Payload::ComponentTypeSection(reader) => {
+                for ty in reader {
+                    scope.types.push(match ty.map_err(malformed)? {
+                        ComponentType::Instance(declarations) => {
+                            Some(instance_functions(&declarations))
+                        }
+                        _ => None,
+                    });
+                }
+            }
+            Payload::ComponentImportSection(reader) => {
+                for import in reader {
+                    let import = import.map_err(malformed)?;
+                    let name = import.name.0.to_owned();
+                    let mut functions = Vec::new();
+                    match import.ty {
+                        ComponentTypeRef::Instance(index) => {
+                            functions = scope
+                                .types
+                                .get(index as usize)
+                                .cloned()
+                                .flatten()
+                                .unwrap_or_default();
+                            scope.instances.push(ComponentInstanceItem {
+                                import: Some(name.clone()),
+                                functions: functions.clone(),
+                                exports: Vec::new(),
+                            });
+                        }
+                        ComponentTypeRef::Func(_) => {
+                            scope.functions.push(Function::Import(name.clone()))
+                        }
+                        ComponentTypeRef::Module(_) => scope.core_modules.push(None),
+                        ComponentTypeRef::Type(_) => scope.types.push(None),
+                        _ => {}
+                    }
+                    if !top {
+                        continue;
+                    }
+                    let kind = ComponentItemKind::from(import.ty.kind());
+                    let category = match kind {
+                        ComponentItemKind::Instance => Some(&name[..]),
+                        ComponentItemKind::Function => Some(ROOT),
+                        _ => None,
+                    }
+                    .map(|module| {
+                        wasi::interface_category(module)
+                            .unwrap_or("unknown")
+                            .to_owned()
+                    });
+                    component.imports.push(ComponentItem {
+                        name,
+                        kind,
+                        category,
+                        functions,
+                    });
+                }
+            }
+            Payload::ComponentAliasSection(reader) => {
+                for alias in reader {
+                    match alias.map_err(malformed)? {
+                        ComponentAlias::InstanceExport {
+                            kind,
+                            instance_index,
+                            name,
+                        } => match kind {
+                            ComponentExternalKind::Func => {
+                                scope.functions.push(Function::InstanceExport {
+                                    instance: instance_index,
+                                    name: name.to_owned(),
+                                })
+                            }
+                            ComponentExternalKind::Instance => {
+                                scope.instances.push(ComponentInstanceItem::default())
+                            }
+                            ComponentExternalKind::Module => scope.core_modules.push(None),
+                            ComponentExternalKind::Type => scope.types.push(None),
+                            _ => {}
+                        },
+                        ComponentAlias::CoreInstanceExport {
+                            kind,
+                            instance_index,
+                            name,
+                        } => {
+                            if let Some(items) = scope.core_items(core_kind(kind)) {
+                                items.push(CoreItem::Export {
+                                    instance: instance_index,
+                                    name: name.to_owned(),
+                                });
+                            }
+                        }
+                        ComponentAlias::Outer { kind, .. } => match kind {
+                            ComponentOuterAliasKind::CoreModule => scope.core_modules.push(None),
+                            ComponentOuterAliasKind::Type => scope.types.push(None),
+                            _ => {}
+                        },
+                    }
+                }
+            }
+            Payload::ComponentCanonicalSection(reader) => {
+                for function in reader {
+                    match function.map_err(malformed)? {
+                        CanonicalFunction::Lift { .. } => scope.functions.push(Function::Unknown),
+                        CanonicalFunction::Lower { func_index, .. } => {
+                            scope.core_functions.push(CoreItem::Lowered(func_index))
+                        }
+                        builtin => scope
+                            .core_functions
+                            .push(CoreItem::Builtin(builtin_name(&builtin))),
+                    }
+                }
+            }
+            Payload::ComponentInstanceSection(reader) => {
+                for instance in reader {
+                    scope.instances.push(match instance.map_err(malformed)? {
+                        ComponentInstance::FromExports(exports) => {
+                            let exports: Vec<_> = exports
+                                .iter()
+                                .filter(|e| e.kind == ComponentExternalKind::Func)
+                                .map(|e| (e.name.0.to_owned(), e.index))
+                                .collect();
+                            ComponentInstanceItem {
+                                import: None,
+                                functions: exports.iter().map(|(name, _)| name.clone()).collect(),
+                                exports,
+                            }
+                        }
+                        // Only the nested component knows what it exports
+                        ComponentInstance::Instantiate { .. } => ComponentInstanceItem::default(),
+                    });
+                }
+            }
+            Payload::ComponentExportSection(reader) => {
+                for export in reader {
+                    let export = export.map_err(malformed)?;
+                    let index = export.index as usize;
+                    let mut functions = Vec::new();
+                    // Exports are items of their own, with their own index
+                    match export.kind {
+                        ComponentExternalKind::Instance => {
+                            let instance = scope.instances.get(index).cloned().unwrap_or_default();
+                            functions = match export.ty {
+                                Some(ComponentTypeRef::Instance(ty)) => {
+                                    scope.types.get(ty as usize).cloned().flatten()
+                                }
+                                _ => None,
+                            }
+                            .unwrap_or_else(|| instance.functions.clone());
+                            scope.instances.push(instance);
+                        }
+                        ComponentExternalKind::Func => {
+                            let function = scope.functions.get(index).cloned();
+                            scope.functions.push(function.unwrap_or(Function::Unknown));
+                        }
+                        ComponentExternalKind::Module => {
+                            let module = scope.core_modules.get(index).copied().flatten();
+                            scope.core_modules.push(module);
+                        }
+                        ComponentExternalKind::Type => scope.types.push(None),
+                        _ => {}
+                    }
+                    if top {
+                        component.exports.push(ComponentItem {
+                            name: export.name.0.to_owned(),
+                            kind: export.kind.into(),
+                            category: None,
+                            functions,
+                        });
+                    }
+                }
+            }
+            Payload::CustomSection(reader) => {
+                // A broken name section only costs us the names
+                if let KnownCustom::ComponentName(reader) = reader.as_known() {
+                    for name in reader.into_iter().flatten() {
+                        if let ComponentName::CoreModules(names) = name {
+                            scope.module_names.extend(
+                                names
+                                    .into_iter()
+                                    .flatten()
+                                    .map(|n| (n.index, n.name.to_owned())),
+                            );
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    // The modules satisfying another module's imports of an older WASI
+    // snapshot adapt it to the interfaces of the component
+    let mut adapters: Vec<u32> = Vec::new();
+    for nested in &modules {
+        for link in &nested.links {
+            let snapshot = WasiSnapshot::from_module_name(&link.module);
+            if let (Some(snapshot), ImportSource::Module { module, .. }) = (snapshot, &link.source)
+            {
+                if snapshot != WasiSnapshot::Preview2 {
+                    adapters.push(*module);
+                }
+            }
+        }
+    }
+    for (index, nested) in modules.iter_mut().enumerate() {
+        let named = nested
+            .name
+            .as_ref()
+            .is_some_and(|name| name.starts_with("wit-component:adapter:"));
+        nested.adapter = named || adapters.contains(&(index as u32));
+    }
+    let is_adapter: Vec<bool> = modules.iter().map(|m| m.adapter).collect();
+    for link in modules.iter_mut().flat_map(|m| &mut m.links) {
+        if let ImportSource::Module { module, export } = &link.source {
+            if is_adapter[*module as usize] {
+                link.source = ImportSource::Adapter {
+                    module: *module,
+                    export: export.clone(),
+                };
+            }
+        }
+    }
+    let reaches = reaches(&modules);
+    for (link, reaches) in modules.iter_mut().flat_map(|m| &mut m.links).zip(reaches) {
+        link.reaches = reaches;
+    }
+    Ok(ParsedComponent {
+        assumptions: component,
+        modules,
+    })
+}
+
+impl Scope {
+    fn core_items(&mut self, kind: ExternalKind) -> Option<&mut Vec<CoreItem>> {
+        match kind {
+            ExternalKind::Func => Some(&mut self.core_functions),
+            ExternalKind::Table => Some(&mut self.core_tables),
+            ExternalKind::Memory => Some(&mut self.core_memories),
+            ExternalKind::Global => Some(&mut self.core_globals),
+            ExternalKind::Tag => Some(&mut self.core_tags),
+            _ => None,
+        }
+    }
+
+    fn core_item(&self, kind: ExternalKind, index: u32) -> Option<&CoreItem> {
+        let items = match kind {
+            ExternalKind::Func => &self.core_functions,
+            ExternalKind::Table => &self.core_tables,
+            ExternalKind::Memory => &self.core_memories,
+            ExternalKind::Global => &self.core_globals,
+            ExternalKind::Tag => &self.core_tags,
+            _ => return None,
+        };
+        items.get(index as usize)
+    }
+
+    /// The position of the module a core module index refers to
+    fn module(&self, index: u32) -> Option<usize> {
+        self.core_modules.get(index as usize).copied().flatten()
+    }
+
+    /// Name the modules of the component and trace the imports of every
+    /// module it instantiates
+    fn link(self, modules: &mut [NestedModule]) {
+        for (index, name) in &self.module_names {
+            if let Some(position) = self.module(*index) {
+                modules[position].name = Some(name.clone());
+            }
+        }
+        let mut linked = Vec::new();
+        for instance in &self.core_instances {
+            let (position, args) = match instance {
+                CoreInstance::Instantiate { module, args } => match self.module(*module) {
+                    Some(position) if !linked.contains(&position) => (position, args),
+                    _ => continue,
+                },
+                CoreInstance::Exports(_) => continue,
+            };
+            linked.push(position);
+            let links = modules[position]
+                .module
+                .imports
+                .iter()
+                .map(|import| {
+                    let kind = import_kind(import.ty);
+                    let source = match arg(args, import.module) {
+                        Some(instance) => {
+                            self.resolve_export(modules, instance, kind, import.name, 0)
+                        }
+                        None => ImportSource::Unresolved,
+                    };
+                    ImportLink {
+                        module: import.module.to_owned(),
+                        field: import.name.to_owned(),
+                        source,
+                        reaches: Vec::new(),
+                    }
+                })
+                .collect();
+            modules[position].links = links;
+        }
+    }
+
+    /// Where the export `name` of a core instance comes from
+    fn resolve_export(
+        &self,
+        modules: &[NestedModule],
+        instance: u32,
+        kind: ExternalKind,
+        name: &str,
+        depth: usize,
+    ) -> ImportSource {
+        if depth > MAX_DEPTH {
+            return ImportSource::Unresolved;
+        }
+        match self.core_instances.get(instance as usize) {
+            Some(CoreInstance::Exports(exports)) => {
+                match exports.iter().find(|(n, k, _)| n == name && *k == kind) {
+                    Some(&(_, _, index)) => self.resolve_item(modules, kind, index, depth + 1),
+                    None => ImportSource::Unresolved,
+                }
+            }
+            Some(CoreInstance::Instantiate { module, args }) => {
+                let position = match self.module(*module) {
+                    Some(position) => position,
+                    None => return ImportSource::Unresolved,
+                };
+                let module = &modules[position].module;
+                let index = match module
+                    .exports
+                    .iter()
+                    .find(|e| e.name == name && core_kind(e.kind) == kind)
+                {
+                    Some(export) => export.index,
+                    None => return ImportSource::Unresolved,
+                };
+                // Modules can pass their imports on
+                if let Some(import) = module.imported_item(kind, index) {
+                    return match arg(args, import.module) {
+                        Some(instance) => {
+                            self.resolve_export(modules, instance, kind, import.name, depth + 1)
+                        }
+                        None => ImportSource::Unresolved,
+                    };
+                }
+                // wit-component breaks the cycle between a module and its
+                // adapter with a shim module whose functions call through
+                // a table, which another module fills in later
+                if kind == ExternalKind::Func {
+                    if let Some((table, slot)) = trampoline(module, index) {
+                        if let Some(source) =
+                            self.resolve_slot(modules, instance, table, slot, depth + 1)
+                        {
+                            return source;
+                        }
+                    }
+                }
+                ImportSource::Module {
+                    module: position as u32,
+                    export: name.to_owned(),
+                }
+            }
+            None => ImportSource::Unresolved,
+        }
+    }
+
+    fn resolve_item(
+        &self,
+        modules: &[NestedModule],
+        kind: ExternalKind,
+        index: u32,
+        depth: usize,
+    ) -> ImportSource {
+        match self.core_item(kind, index) {
+            Some(CoreItem::Export { instance, name }) => {
+                self.resolve_export(modules, *instance, kind, name, depth)
+            }
+            Some(CoreItem::Lowered(function)) => self.resolve_function(*function, depth),
+            Some(CoreItem::Builtin(name)) => ImportSource::Builtin {
+                name: name.to_string(),
+            },
+            None => ImportSource::Unresolved,
+        }
+    }
+
+    /// Where a function of the component comes from
+    fn resolve_function(&self, index: u32, depth: usize) -> ImportSource {
+        if depth > MAX_DEPTH {
+            return ImportSource::Unresolved;
+        }
+        match self.functions.get(index as usize) {
+            Some(Function::Import(name)) => ImportSource::ComponentImport {
+                import: name.clone(),
+                function: None,
+            },
+            Some(Function::InstanceExport { instance, name }) => {
+                let instance = match self.instances.get(*instance as usize) {
+                    Some(instance) => instance,
+                    None => return ImportSource::Unresolved,
+                };
+                if let Some(import) = &instance.import {
+                    return ImportSource::ComponentImport {
+                        import: import.clone(),
+                        function: Some(name.clone()),
+                    };
+                }
+                match instance.exports.iter().find(|(n, _)| n == name) {
+                    Some(&(_, function)) => self.resolve_function(function, depth + 1),
+                    None => ImportSource::Unresolved,
+                }
+            }
+            Some(Function::Unknown) | None => ImportSource::Unresolved,
+        }
+    }
+
+    /// The instance defining the table exported as `name`, along with the
+    /// index of the table in its module
+    fn resolve_table(
+        &self,
+        modules: &[NestedModule],
+        instance: u32,
+        name: &str,
+        depth: usize,
+    ) -> Option<(u32, u32)> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        match self.core_instances.get(instance as usize)? {
+            CoreInstance::Exports(exports) => {
+                let &(_, _, index) = exports
+                    .iter()
+                    .find(|(n, k, _)| n == name && *k == ExternalKind::Table)?;
+                match self.core_item(ExternalKind::Table, index)? {
+                    CoreItem::Export { instance, name } => {
+                        self.resolve_table(modules, *instance, name, depth + 1)
+                    }
+                    _ => None,
+                }
+            }
+            CoreInstance::Instantiate { module, args } => {
+                let module = &modules[self.module(*module)?].module;
+                let export = module
+                    .exports
+                    .iter()
+                    .find(|e| e.name == name && e.kind == ExternalKind::Table)?;
+                match module.imported_item(ExternalKind::Table, export.index) {
+                    Some(import) => self.resolve_table(
+                        modules,
+                        arg(args, import.module)?,
+                        import.name,
+                        depth + 1,
+                    ),
+                    None => Some((instance, export.index)),
+                }
+            }
+        }
+    }
+
+    /// Where the function another module places in a slot of the table of
+    /// a core instance comes from
+    fn resolve_slot(
+        &self,
+        modules: &[NestedModule],
+        instance: u32,
+        table: u32,
+        slot: u64,
+        depth: usize,
+    ) -> Option<ImportSource> {
+        for core_instance in &self.core_instances {
+            let (module, args) = match core_instance {
+                CoreInstance::Instantiate { module, args } => match self.module(*module) {
+                    Some(position) => (&modules[position].module, args),
+                    None => continue,
+                },
+                CoreInstance::Exports(_) => continue,
+            };
+            for &(filled, filled_slot, function) in &module.table_slots {
+                if filled_slot != slot {
+                    continue;
+                }
+                let filled = match module.imported_item(ExternalKind::Table, filled) {
+                    Some(import) => arg(args, import.module)
+                        .and_then(|i| self.resolve_table(modules, i, import.name, depth + 1)),
+                    None => None,
+                };
+                if filled != Some((instance, table)) {
+                    continue;
+                }
+                let import = module.imported_item(ExternalKind::Func, function)?;
+                let instance = arg(args, import.module)?;
+                return Some(self.resolve_export(
+                    modules,
+                    instance,
+                    ExternalKind::Func,
+                    import.name,
+                    depth + 1,
+                ));
+            }
+        }
+        None
+    }
+}
+
+impl ComponentAssumptions {
+    /// The functions the component imports, as imports of the function
+    /// from a module named after its interface, like the core modules
+    /// inside the component import them. Functions imported on their own
+    /// come from the `$root` module.
+    pub(crate) fn imported_functions(&self) -> Vec<Import> {
+        let function = |module: &str, field: &str| Import {
+            module: module.to_owned(),
+            field: field.to_owned(),
+            kind: ImportKind::Function,
+            signature: None,
+            reachability: None,
+        };
+        let mut functions = Vec::new();
+        for import in &self.imports {
+            match import.kind {
+                ComponentItemKind::Instance => functions.extend(
+                    import
+                        .functions
+                        .iter()
+                        .map(|field| function(&import.name, field)),
+                ),
+                ComponentItemKind::Function => functions.push(function(ROOT, &import.name)),
+                _ => {}
+            }
+        }
+        functions
+    }
+}
+
+/// For every link of every module, the functions of component imports the
+/// export satisfying it can call
+fn reaches(modules: &[NestedModule]) -> Vec<Vec<String>> {
+    let graphs: Vec<_> = modules.iter().map(|m| CallGraph::new(&m.module)).collect();
+    let mut reaches = Vec::new();
+    for link in modules.iter().flat_map(|m| &m.links) {
+        let mut found = Vec::new();
+        if let ImportSource::Module { module, export } | ImportSource::Adapter { module, export } =
+            &link.source
+        {
+            let mut visited = Vec::new();
+            reach(modules, &graphs, *module, export, &mut visited, &mut found);
+        }
+        reaches.push(found);
+    }
+    reaches
+}
+
+fn reach<'a>(
+    modules: &'a [NestedModule],
+    graphs: &[CallGraph],
+    module: u32,
+    export: &'a str,
+    visited: &mut Vec<(u32, &'a str)>,
+    found: &mut Vec<String>,
+) {
+    if visited.contains(&(module, export)) {
+        return;
+    }
+    visited.push((module, export));
+    let nested = &modules[module as usize];
+    let function = match nested
+        .module
+        .exports
+        .iter()
+        .find(|e| e.name == export && core_kind(e.kind) == ExternalKind::Func)
+    {
+        Some(export) => export.index,
+        None => return,
+    };
+    let reached = graphs[module as usize].reachable_from(&[function]);
+    let functions = nested
+        .links
+        .iter()
+        .zip(&nested.module.imports)
+        .filter(|(_, import)| import_kind(import.ty) == ExternalKind::Func)
+        .map(|(link, _)| link);
+    for (link, _) in functions.zip(reached).filter(|(_, reached)| *reached) {
+        match &link.source {
+            ImportSource::ComponentImport { import, function } => {
+                let name = match function {
+                    Some(function) => format!("{}::{}", import, function),
+                    None => import.clone(),
+                };
+                if !found.contains(&name) {
+                    found.push(name);
+                }
+            }
+            ImportSource::Module { module, export } | ImportSource::Adapter { module, export } => {
+                reach(modules, graphs, *module, export, visited, found)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The table and slot a function calls through, if all it does is pass its
+/// parameters on to a `call_indirect` of a constant slot
+fn trampoline(module: &ParsedModule, function: u32) -> Option<(u32, u64)> {
+    let body = module
+        .bodies
+        .get(function.checked_sub(module.imported_functions)? as usize)?;
+    let mut operators = body.get_operators_reader().ok()?;
+    let mut slot = None;
+    loop {
+        match operators.read().ok()? {
+            Operator::LocalGet { .. } if slot.is_none() => {}
+            Operator::I32Const { value } if slot.is_none() => slot = Some(value as u32 as u64),
+            Operator::I64Const { value } if slot.is_none() => slot = Some(value as u64),
+            Operator::CallIndirect { table_index, .. } => {
+                return match operators.read().ok()? {
+                    Operator::End => Some((table_index, slot?)),
+                    _ => None,
+                };
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// The core instance passed to a module as `name`
+fn arg(args: &[(String, u32)], name: &str) -> Option<u32> {
+    args.iter()
+        .find(|(n, _)| n == name)
+        .map(|&(_, index)| index)
+}
+
+/// The kind of a core item, not telling exact function types apart
+fn core_kind(kind: ExternalKind) -> ExternalKind {
+    match kind {
+        ExternalKind::FuncExact => ExternalKind::Func,
+        kind => kind,
+    }
+}
+
+fn import_kind(ty: TypeRef) -> ExternalKind {
+    match ty {
+        TypeRef::Func(_) | TypeRef::FuncExact(_) => ExternalKind::Func,
+        TypeRef::Table(_) => ExternalKind::Table,
+        TypeRef::Memory(_) => ExternalKind::Memory,
+        TypeRef::Global(_) => ExternalKind::Global,
+        TypeRef::Tag(_) => ExternalKind::Tag,
+    }
+}
+
+/// The name of a canonical function, as the component model names the
+/// functions it provides, like `resource.drop`
+fn builtin_name(function: &CanonicalFunction) -> &'static str {
+    match function {
+        CanonicalFunction::Lift { .. } => "lift",
+        CanonicalFunction::Lower { .. } => "lower",
+        CanonicalFunction::ResourceNew { .. } => "resource.new",
+        CanonicalFunction::ResourceDrop { .. } => "resource.drop",
+        CanonicalFunction::ResourceDropAsync { .. } => "resource.drop-async",
+        CanonicalFunction::ResourceRep { .. } => "resource.rep",
+        CanonicalFunction::ThreadSpawnRef { .. } => "thread.spawn-ref",
+        CanonicalFunction::ThreadSpawnIndirect { .. } => "thread.spawn-indirect",
+        CanonicalFunction::ThreadAvailableParallelism => "thread.available-parallelism",
+        CanonicalFunction::BackpressureInc => "backpressure.inc",
+        CanonicalFunction::BackpressureDec => "backpressure.dec",
+        CanonicalFunction::TaskReturn { .. } => "task.return",
+        CanonicalFunction::TaskCancel => "task.cancel",
+        CanonicalFunction::ContextGet(_) => "context.get",
+        CanonicalFunction::ContextSet(_) => "context.set",
+        CanonicalFunction::ThreadYield { .. } => "thread.yield",
+        CanonicalFunction::SubtaskDrop => "subtask.drop",
+        CanonicalFunction::SubtaskCancel { .. } => "subtask.cancel",
+        CanonicalFunction::StreamNew { .. } => "stream.new",
+        CanonicalFunction::StreamRead { .. } => "stream.read",
+        CanonicalFunction::StreamWrite { .. } => "stream.write",
+        CanonicalFunction::StreamCancelRead { .. } => "stream.cancel-read",
+        CanonicalFunction::StreamCancelWrite { .. } => "stream.cancel-write",
+        CanonicalFunction::StreamDropReadable { .. } => "stream.drop-readable",
+        CanonicalFunction::StreamDropWritable { .. } => "stream.drop-writable",
+        CanonicalFunction::FutureNew { .. } => "future.new",
+        CanonicalFunction::FutureRead { .. } => "future.read",
+        CanonicalFunction::FutureWrite { .. } => "future.write",
+        CanonicalFunction::FutureCancelRead { .. } => "future.cancel-read",
+        CanonicalFunction::FutureCancelWrite { .. } => "future.cancel-write",
+        CanonicalFunction::FutureDropReadable { .. } => "future.drop-readable",
+        CanonicalFunction::FutureDropWritable { .. } => "future.drop-writable",
+        CanonicalFunction::ErrorContextNew { .. } => "error-context.new",
+        CanonicalFunction::ErrorContextDebugMessage { .. } => "error-context.debug-message",
+        CanonicalFunction::ErrorContextDrop => "error-context.drop",
+        CanonicalFunction::WaitableSetNew => "waitable-set.new",
+        CanonicalFunction::WaitableSetWait { .. } => "waitable-set.wait",
+        CanonicalFunction::WaitableSetPoll { .. } => "waitable-set.poll",
+        CanonicalFunction::WaitableSetDrop => "waitable-set.drop",
+        CanonicalFunction::WaitableJoin => "waitable.join",
+        CanonicalFunction::ThreadIndex => "thread.index",
+        CanonicalFunction::ThreadNewIndirect { .. } => "thread.new-indirect",
+        CanonicalFunction::ThreadSuspendToSuspended { .. } => "thread.suspend-to-suspended",
+        CanonicalFunction::ThreadSuspend { .. } => "thread.suspend",
+        CanonicalFunction::ThreadSuspendTo { .. } => "thread.suspend-to",
+        CanonicalFunction::ThreadUnsuspend => "thread.unsuspend",
+        CanonicalFunction::ThreadYieldToSuspended { .. } => "thread.yield-to-suspended",
+    }
+}
+
+/// The names of the functions an instance type exports
+fn instance_functions(declarations: &[InstanceTypeDeclaration]) -> Vec<String> {
+    declarations
+        .iter()
+        .filter_map(|declaration| match declaration {
+            InstanceTypeDeclaration::Export {
+                name,
+                ty: ComponentTypeRef::Func(_),
+            } => Some(name.0.to_owned()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, fields};
+    use crate::{Proposal, WasiSnapshot};
+
+    fn names(items: &[ComponentItem]) -> Vec<(&str, ComponentItemKind, Option<&str>)> {
+        items
+            .iter()
+            .map(|i| (i.name.as_str(), i.kind, i.category.as_deref()))
+            .collect()
+    }
+
+    fn sources(module: &CoreModule) -> Vec<(String, &ImportSource, &[String])> {
+        module
+            .links
+            .iter()
+            .map(|l| {
+                (
+                    format!("{}::{}", l.module, l.field),
+                    &l.source,
+                    l.reaches.as_slice(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lists_imported_interfaces_and_their_functions() {
+        let report = analyze(
+            r#"(component
+                (type $environment (instance
+                    (export "get-environment" (func))
+                    (export "get-arguments" (func))))
+                (import "wasi:cli/environment@0.2.0" (instance (type $environment)))
+                (import "wasi:sockets/tcp@0.2.0" (instance (export "connect" (func))))
+                (import "my:app/logger" (instance (export "log" (func))))
+                (import "tick" (func))
+                (import "config" (type (sub resource))))"#,
+        );
+        let component = report.assumptions.component.as_ref().unwrap();
+        assert_eq!(
+            names(&component.imports),
+            vec![
+                (
+                    "wasi:cli/environment@0.2.0",
+                    ComponentItemKind::Instance,
+                    Some("environment")
+                ),
+                (
+                    "wasi:sockets/tcp@0.2.0",
+                    ComponentItemKind::Instance,
+                    Some("network")
+                ),
+                (
+                    "my:app/logger",
+                    ComponentItemKind::Instance,
+                    Some("unknown")
+                ),
+                ("tick", ComponentItemKind::Function, Some("unknown")),
+                ("config", ComponentItemKind::Type, None),
+            ]
+        );
+        assert_eq!(
+            component.imports[0].functions,
+            vec!["get-environment", "get-arguments"]
+        );
+
+        let wasi = &report.assumptions.wasi;
+        assert_eq!(wasi.snapshots, vec![WasiSnapshot::Preview2]);
+        assert_eq!(
+            fields(&wasi.environment),
+            vec!["get-environment", "get-arguments"]
+        );
+        assert_eq!(fields(&wasi.network), vec!["connect"]);
+        assert!(wasi.signature_mismatches.is_empty());
+        let unknown: Vec<_> = report
+            .assumptions
+            .unknown
+            .iter()
+            .map(|i| format!("{}::{}", i.module, i.field))
+            .collect();
+        assert_eq!(unknown, vec!["my:app/logger::log", "$root::tick"]);
+    }
+
+    #[test]
+    fn follows_re_exported_instances_and_functions() {
+        let report = analyze(
+            r#"(component
+                (import "wasi:random/random@0.2.0" (instance $random
+                    (export "get-random-u64" (func (result u64)))))
+                (alias export $random "get-random-u64" (func $get))
+                (instance $api (export "next" (func $get)))
+                (export "my:app/api" (instance $api))
+                (export "random" (instance $random))
+                (export "next" (func $get)))"#,
+        );
+        let component = report.assumptions.component.unwrap();
+        assert_eq!(
+            names(&component.exports),
+            vec![
+                ("my:app/api", ComponentItemKind::Instance, None),
+                ("random", ComponentItemKind::Instance, None),
+                ("next", ComponentItemKind::Function, None),
+            ]
+        );
+        assert_eq!(component.exports[0].functions, vec!["next"]);
+        assert_eq!(component.exports[1].functions, vec!["get-random-u64"]);
+    }
+
+    #[test]
+    fn counts_nested_modules_and_components() {
+        let report = analyze(
+            r#"(component
+                (import "wasi:cli/exit@0.2.0" (instance (export "exit" (func))))
+                (core module
+                    (import "env" "inner" (func)))
+                (component
+                    (import "wasi:filesystem/types@0.2.0" (instance
+                        (export "read" (func))))
+                    (core module)
+                    (component))
+                (instance $run)
+                (export "wasi:cli/run@0.2.0" (instance $run)))"#,
+        );
+        let component = report.assumptions.component.as_ref().unwrap();
+        assert_eq!(component.modules, 1);
+        assert_eq!(component.components, 1);
+        // The imports of the nested component are satisfied by the outer one
+        assert_eq!(
+            names(&component.imports),
+            vec![(
+                "wasi:cli/exit@0.2.0",
+                ComponentItemKind::Instance,
+                Some("process")
+            )]
+        );
+        assert_eq!(fields(&report.assumptions.wasi.process), vec!["exit"]);
+        assert!(report.assumptions.wasi.file_system.is_empty());
+        assert_eq!(component.exports[0].name, "wasi:cli/run@0.2.0");
+    }
+
+    #[test]
+    fn core_modules_are_not_components() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "sched_yield" (func (result i32))))"#,
+        );
+        assert!(report.assumptions.component.is_none());
+    }
+
+    #[test]
+    fn traces_imports_of_core_modules() {
+        let report = analyze(
+            r#"(component
+                (import "my:app/logger" (instance $logger (export "log" (func))))
+                (core module $lib
+                    (import "host" "log" (func $log))
+                    (func (export "helper") call $log))
+                (core module $main
+                    (import "lib" "helper" (func))
+                    (import "host" "log" (func))
+                    (import "canon" "drop" (func (param i32)))
+                    (func (export "run")))
+                (core module $unused
+                    (import "host" "log" (func)))
+                (alias export $logger "log" (func $log))
+                (core func $log_lowered (canon lower (func $log)))
+                (core instance $host (export "log" (func $log_lowered)))
+                (core instance $lib_instance (instantiate $lib (with "host" (instance $host))))
+                (type $resource (resource (rep i32)))
+                (core func $drop (canon resource.drop $resource))
+                (core instance $canon (export "drop" (func $drop)))
+                (core instance (instantiate $main
+                    (with "lib" (instance $lib_instance))
+                    (with "host" (instance $host))
+                    (with "canon" (instance $canon)))))"#,
+        );
+        let component = report.assumptions.component.unwrap();
+        let modules = &component.core_modules;
+        let log = ImportSource::ComponentImport {
+            import: "my:app/logger".to_owned(),
+            function: Some("log".to_owned()),
+        };
+        assert_eq!(
+            sources(&modules[0]),
+            vec![("host::log".to_owned(), &log, &[][..])]
+        );
+        assert_eq!(
+            sources(&modules[1]),
+            vec![
+                (
+                    "lib::helper".to_owned(),
+                    &ImportSource::Module {
+                        module: 0,
+                        export: "helper".to_owned()
+                    },
+                    &["my:app/logger::log".to_owned()][..]
+                ),
+                ("host::log".to_owned(), &log, &[][..]),
+                (
+                    "canon::drop".to_owned(),
+                    &ImportSource::Builtin {
+                        name: "resource.drop".to_owned()
+                    },
+                    &[][..]
+                ),
+            ]
+        );
+        assert!(modules[2].links.is_empty());
+    }
+
+    #[test]
+    fn traces_imports_within_nested_components() {
+        let report = analyze(
+            r#"(component
+                (component $inner
+                    (import "tick" (func $tick))
+                    (core module $clock
+                        (import "host" "tick" (func)))
+                    (core func $tick_lowered (canon lower (func $tick)))
+                    (core instance $host (export "tick" (func $tick_lowered)))
+                    (core instance (instantiate $clock (with "host" (instance $host)))))
+                (core module $main
+                    (import "inner" "run" (func)))
+                (core instance $empty)
+                (core instance (instantiate $main (with "inner" (instance $empty)))))"#,
+        );
+        let modules = report.assumptions.component.unwrap().core_modules;
+        // The module of the nested component is linked within its scope
+        assert_eq!(
+            sources(&modules[0]),
+            vec![(
+                "host::tick".to_owned(),
+                &ImportSource::ComponentImport {
+                    import: "tick".to_owned(),
+                    function: None
+                },
+                &[][..]
+            )]
+        );
+        assert_eq!(
+            sources(&modules[1]),
+            vec![("inner::run".to_owned(), &ImportSource::Unresolved, &[][..])]
+        );
+    }
+
+    #[test]
+    fn analyzes_each_core_module() {
+        let report = analyze(
+            r#"(component
+                (core module
+                    (import "wasi_snapshot_preview1" "proc_exit" (func (param i32))))
+                (core module
+                    (memory 1)
+                    (func (param i32) (result i32)
+                        local.get 0
+                        i32.extend16_s)))"#,
+        );
+        let modules = report.assumptions.component.unwrap().core_modules;
+        assert_eq!(modules.len(), 2);
+        assert_eq!(
+            fields(&modules[0].report.assumptions.wasi.process),
+            vec!["proc_exit"]
+        );
+        let proposal = &modules[1].report.proposals[0];
+        assert_eq!(proposal.proposal, Proposal::SignExtension);
+        // Offsets are the ones in the component
+        assert!(proposal.offset > modules[1].offset);
+    }
+
+    #[test]
+    fn names_canonical_built_ins() {
+        let names: Vec<_> = [
+            CanonicalFunction::ResourceDrop { resource: 0 },
+            CanonicalFunction::ContextGet(0),
+            CanonicalFunction::TaskCancel,
+            CanonicalFunction::WaitableSetNew,
+            CanonicalFunction::ThreadAvailableParallelism,
+            CanonicalFunction::ErrorContextDrop,
+        ]
+        .iter()
+        .map(builtin_name)
+        .collect();
+        assert_eq!(
+            names,
+            vec![
+                "resource.drop",
+                "context.get",
+                "task.cancel",
+                "waitable-set.new",
+                "thread.available-parallelism",
+                "error-context.drop",
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_the_proposals_of_core_modules() {
+        let report = analyze(
+            r#"(component
+                (core module
+                    (func (param i32) (result i32)
+                        local.get 0
+                        i32.extend8_s))
+                (core module
+                    (memory 1)
+                    (func (param i32) (result i32)
+                        local.get 0
+                        i32.extend16_s
+                        i32.const 0
+                        i32.const 0
+                        memory.fill
+                        local.get 0)))"#,
+        );
+        let proposals: Vec<_> = report.proposals.iter().map(|p| p.proposal).collect();
+        assert_eq!(
+            proposals,
+            vec![Proposal::SignExtension, Proposal::BulkMemory]
+        );
+        let modules = report.assumptions.component.unwrap().core_modules;
+        assert_eq!(report.proposals[0], modules[0].report.proposals[0]);
+        assert!(report.proposals[1].offset > modules[1].offset);
+    }
+}
@@ -0,0 +1,371 @@
+use crate::{
+    module::ParsedModule, names::FunctionNames, profile::Classifier, proposals, ExportMismatch,
+    Import, Profile, Signature, ValueType,
+};
+
+/// The interface version kontrolleur validates contracts against when
+/// neither the contract nor the caller chooses one
+pub const LATEST_INTERFACE_VERSION: u32 = 8;
+
+/// The first float operator of a contract function. Chains reject
+/// contracts using floats since they may compute differently on different
+/// machines.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FloatOperator {
+    pub function: u32,
+    pub function_name: Option<String>,
+    /// The operator in the text format, like `f64.add`
+    pub operator: String,
+}
+
+/// The imports and exports of a CosmWasm contract, validated against an
+/// interface version
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CosmWasmAssumptions {
+    /// The version of the contract's `interface_version_*` export
+    pub interface_version: Option<u32>,
+    /// The version the contract was validated against: the one asked for,
+    /// else the one the contract declares, else the latest
+    pub validated_version: u32,
+    /// The exports the chain calls but the contract lacks
+    pub missing_exports: Vec<String>,
+    pub export_mismatches: Vec<ExportMismatch>,
+    /// Imports the validated interface version does not provide. Chains
+    /// provide nothing but the `env` functions of the interface.
+    pub disallowed: Vec<Import>,
+    pub float_operators: Vec<FloatOperator>,
+    pub storage: Vec<Import>,
+    pub addresses: Vec<Import>,
+    pub crypto: Vec<Import>,
+    pub queries: Vec<Import>,
+    pub debug: Vec<Import>,
+    /// `env` imports of no interface version
+    pub unknown: Vec<Import>,
+}
+
+#[derive(Clone, Copy)]
+enum Category {
+    Storage,
+    Addresses,
+    Crypto,
+    Queries,
+    Debug,
+}
+
+/// The calls of the interface and the version that introduced them
+#[rustfmt::skip]
+const CALLS: &[(&str, Category, u32)] = &[
+    ("db_read", Category::Storage, 7),
+    ("db_write", Category::Storage, 7),
+    ("db_remove", Category::Storage, 7),
+    ("db_scan", Category::Storage, 7),
+    ("db_next", Category::Storage, 7),
+    ("db_next_key", Category::Storage, 8),
+    ("db_next_value", Category::Storage, 8),
+    ("addr_validate", Category::Addresses, 7),
+    ("addr_canonicalize", Category::Addresses, 7),
+    ("addr_humanize", Category::Addresses, 7),
+    ("secp256k1_verify", Category::Crypto, 7),
+    ("secp256k1_recover_pubkey", Category::Crypto, 7),
+    ("ed25519_verify", Category::Crypto, 7),
+    ("ed25519_batch_verify", Category::Crypto, 7),
+    ("secp256r1_verify", Category::Crypto, 8),
+    ("secp256r1_recover_pubkey", Category::Crypto, 8),
+    ("bls12_381_aggregate_g1", Category::Crypto, 8),
+    ("bls12_381_aggregate_g2", Category::Crypto, 8),
+    ("bls12_381_pairing_equality", Category::Crypto, 8),
+    ("bls12_381_hash_to_g1", Category::Crypto, 8),
+    ("bls12_381_hash_to_g2", Category::Crypto, 8),
+    ("query_chain", Category::Queries, 7),
+    ("debug", Category::Debug, 7),
+    ("abort", Category::Debug, 8),
+];
+
+impl CosmWasmAssumptions {
+    /// Recognize a CosmWasm contract by its interface version marker or its
+    /// imports, and validate it against `version`, if given. With a
+    /// version, the binary is validated even if it does not look like a
+    /// contract.
+    pub(crate) fn detect(module: &ParsedModule, version: Option<u32>) -> Option<Self> {
+        let interface_version = module.exports.iter().find_map(|e| {
+            e.name
+                .strip_prefix("interface_version_")
+                .and_then(|v| v.parse().ok())
+        });
+        let imports = module.imports.iter().any(|i| {
+            i.module == "env" && ["db_read", "addr_validate", "query_chain"].contains(&i.name)
+        });
+        if version.is_none() && interface_version.is_none() && !imports {
+            return None;
+        }
+        let validated_version = version
+            .or(interface_version)
+            .unwrap_or(LATEST_INTERFACE_VERSION);
+
+        let i32s = |count| vec![ValueType::I32; count];
+        let required = [
+            ("allocate", i32s(1), i32s(1)),
+            ("deallocate", i32s(1), Vec::new()),
+            ("instantiate", i32s(3), i32s(1)),
+        ];
+        let mut missing_exports = Vec::new();
+        let mut export_mismatches = Vec::new();
+        let marker = format!("interface_version_{}", validated_version);
+        if module.export_signature(&marker).is_none() {
+            missing_exports.push(marker);
+        }
+        for (name, params, results) in required {
+            let expected = Signature { params, results };
+            match module.export_signature(name) {
+                None => missing_exports.push(name.to_owned()),
+                Some(signature) if *signature != expected => {
+                    export_mismatches.push(ExportMismatch {
+                        export: name.to_owned(),
+                        signature: signature.clone(),
+                        expected,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        let disallowed = module
+            .imports
+            .iter()
+            .filter(|i| {
+                i.module != "env"
+                    || !CALLS
+                        .iter()
+                        .any(|&(name, _, since)| name == i.name && since <= validated_version)
+            })
+            .map(|i| Import::new(module, i))
+            .collect();
+
+        let names = FunctionNames::new(module);
+        let mut float_operators = Vec::new();
+        for (body, function) in module.bodies.iter().zip(module.imported_functions..) {
+            let mut operators = match body.get_operators_reader() {
+                Ok(operators) => operators,
+                Err(_) => continue,
+            };
+            while let Ok(operator) = operators.read() {
+                if proposals::is_float_operator(&operator) {
+                    float_operators.push(FloatOperator {
+                        function,
+                        function_name: names.find(function).map(String::from),
+                        operator: proposals::instruction_name(&operator),
+                    });
+                    break;
+                }
+            }
+        }
+
+        Some(CosmWasmAssumptions {
+            interface_version,
+            validated_version,
+            missing_exports,
+            export_mismatches,
+            disallowed,
+            float_operators,
+            ..CosmWasmAssumptions::default()
+        })
+    }
+}
+
+impl Classifier for CosmWasmAssumptions {
+    fn classify(&mut self, import: Import) -> Result<(), Import> {
+        if import.module != "env" {
+            return Err(import);
+        }
+        let calls = match CALLS.iter().find(|(name, _, _)| *name == import.field) {
+            Some((_, Category::Storage, _)) => &mut self.storage,
+            Some((_, Category::Addresses, _)) => &mut self.addresses,
+            Some((_, Category::Crypto, _)) => &mut self.crypto,
+            Some((_, Category::Queries, _)) => &mut self.queries,
+            Some((_, Category::Debug, _)) => &mut self.debug,
+            None => &mut self.unknown,
+        };
+        calls.push(import);
+        Ok(())
+    }
+}
+
+impl Profile for CosmWasmAssumptions {
+    fn id(&self) -> &'static str {
+        "cosmwasm"
+    }
+
+    fn name(&self) -> &'static str {
+        "CosmWasm"
+    }
+
+    fn categories(&self) -> Vec<(&'static str, &[Import])> {
+        vec![
+            ("storage", &self.storage),
+            ("addresses", &self.addresses),
+            ("crypto", &self.crypto),
+            ("queries", &self.queries),
+            ("debug", &self.debug),
+        ]
+    }
+
+    fn unknown(&self) -> &[Import] {
+        &self.unknown
+    }
+
+    fn notes(&self) -> Vec<String> {
+        let mut notes = Vec::new();
+        match self.interface_version {
+            Some(version) => notes.push(format!(
+                "The contract declares interface version {}",
+                version
+            )),
+            None => notes.push("The contract declares no interface version".to_owned()),
+        }
+        notes.push(format!(
+            "The contract was validated against interface version {}",
+            self.validated_version
+        ));
+        notes
+    }
+
+    fn errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !(7..=LATEST_INTERFACE_VERSION).contains(&self.validated_version) {
+            errors.push(format!(
+                "interface version {} is not supported, kontrolleur knows versions 7 to {}",
+                self.validated_version, LATEST_INTERFACE_VERSION
+            ));
+        }
+        for export in &self.missing_exports {
+            errors.push(format!("the contract does not export {}", export));
+        }
+        errors.extend(self.export_mismatches.iter().map(|m| m.to_string()));
+        for import in &self.disallowed {
+            errors.push(format!(
+                "{}::{} is not provided by interface version {}",
+                import.module, import.field, self.validated_version
+            ));
+        }
+        for float in &self.float_operators {
+            let function = match &float.function_name {
+                Some(name) => name.clone(),
+                None => format!("function {}", float.function),
+            };
+            errors.push(format!(
+                "{} uses the float operator {}",
+                function, float.operator
+            ));
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, analyze_with, fields};
+    use crate::Analyzer;
+
+    const CONTRACT: &str = r#"(module
+        (import "env" "db_read" (func (param i32) (result i32)))
+        (import "env" "db_next_key" (func (param i32) (result i32)))
+        (import "env" "debug" (func (param i32)))
+        (import "env" "gas" (func (param i32)))
+        (import "wasi_snapshot_preview1" "random_get" (func (param i32 i32) (result i32)))
+        (func (export "interface_version_7"))
+        (func (export "allocate") (param i32) (result i32) i32.const 0)
+        (func (export "deallocate") (param i32))
+        (func (export "instantiate") (param i32 i32 i32) (result i32)
+            i32.const 0))"#;
+
+    fn contract(analyzer: Analyzer, wat: &str) -> CosmWasmAssumptions {
+        analyze_with(analyzer, wat).assumptions.cosmwasm.unwrap()
+    }
+
+    #[test]
+    fn validates_against_the_declared_version() {
+        let contract = contract(Analyzer::new(), CONTRACT);
+        assert_eq!(contract.interface_version, Some(7));
+        assert_eq!(contract.validated_version, 7);
+        assert!(contract.missing_exports.is_empty());
+        assert!(contract.export_mismatches.is_empty());
+        assert_eq!(
+            fields(&contract.disallowed),
+            vec!["db_next_key", "gas", "random_get"]
+        );
+        assert_eq!(fields(&contract.storage), vec!["db_read", "db_next_key"]);
+        assert_eq!(fields(&contract.unknown), vec!["gas"]);
+    }
+
+    #[test]
+    fn chosen_version_overrides_the_declared_one() {
+        let contract = contract(Analyzer::new().cosmwasm(Some(8)), CONTRACT);
+        assert_eq!(contract.interface_version, Some(7));
+        assert_eq!(contract.validated_version, 8);
+        assert_eq!(contract.missing_exports, vec!["interface_version_8"]);
+        assert_eq!(fields(&contract.disallowed), vec!["gas", "random_get"]);
+    }
+
+    #[test]
+    fn knows_interface_versions_7_to_latest() {
+        for (name, _, since) in CALLS {
+            assert!(
+                (7..=LATEST_INTERFACE_VERSION).contains(since),
+                "{} has an unknown version",
+                name
+            );
+        }
+        let unsupported = CosmWasmAssumptions {
+            validated_version: 6,
+            ..CosmWasmAssumptions::default()
+        };
+        assert_eq!(
+            unsupported.errors(),
+            vec!["interface version 6 is not supported, kontrolleur knows versions 7 to 8"]
+        );
+    }
+
+    #[test]
+    fn reports_the_first_float_operator_of_each_function() {
+        let contract = contract(
+            Analyzer::new().cosmwasm(Some(8)),
+            r#"(module
+                (func $average (param f64 f64) (result f64)
+                    local.get 0
+                    local.get 1
+                    f64.add
+                    f64.const 2
+                    f64.div)
+                (func (param i32) (result i32) local.get 0))"#,
+        );
+        assert_eq!(contract.float_operators.len(), 1);
+        let float = &contract.float_operators[0];
+        assert_eq!(float.function_name.as_deref(), Some("average"));
+        assert_eq!(float.operator, "f64.add");
+        assert_eq!(
+            contract.missing_exports,
+            vec![
+                "interface_version_8",
+                "allocate",
+                "deallocate",
+                "instantiate"
+            ]
+        );
+    }
+
+    #[test]
+    fn other_binaries_are_not_contracts() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "random_get"
+                    (func (param i32 i32) (result i32)))
+                (import "env" "log" (func (param i32)))
+                (func (export "allocate") (param i32) (result i32) i32.const 0))"#,
+        );
+        assert!(report.assumptions.cosmwasm.is_none());
+        assert_eq!(fields(&report.assumptions.unknown), vec!["log"]);
+    }
+}
@@ -0,0 +1,357 @@
+use crate::{
+    module::ParsedModule,
+    names::FunctionNames,
+    proposals::{self, Proposal},
+    WasiSnapshot,
+};
+use std::fmt;
+use wasmparser::{Operator, TypeRef};
+
+/// A way a binary can behave differently when run twice with the same
+/// input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum NondeterminismSource {
+    /// WASI calls reading clocks or randomness or depending on scheduling
+    WasiCall,
+    /// Float operators whose NaN results may have any bit pattern
+    NanFloat,
+    /// Relaxed SIMD operators, whose results depend on the hardware
+    RelaxedSimd,
+    /// Atomic operators, which observe the interleaving of threads
+    Atomics,
+    /// Memories shared between threads
+    SharedMemory,
+}
+
+impl NondeterminismSource {
+    /// The identifier of the source, as used in the JSON output
+    pub fn name(self) -> &'static str {
+        match self {
+            NondeterminismSource::WasiCall => "wasi_call",
+            NondeterminismSource::NanFloat => "nan_float",
+            NondeterminismSource::RelaxedSimd => "relaxed_simd",
+            NondeterminismSource::Atomics => "atomics",
+            NondeterminismSource::SharedMemory => "shared_memory",
+        }
+    }
+}
+
+impl fmt::Display for NondeterminismSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            NondeterminismSource::WasiCall => "nondeterministic WASI calls",
+            NondeterminismSource::NanFloat => "float operators that can produce NaN",
+            NondeterminismSource::RelaxedSimd => "relaxed SIMD operators",
+            NondeterminismSource::Atomics => "atomic operators",
+            NondeterminismSource::SharedMemory => "shared memory",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The uses of a source of nondeterminism in one function, or in the
+/// module for shared memories
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Nondeterminism {
+    pub source: NondeterminismSource,
+    /// The position of the core module the source is in, among the core
+    /// modules of a component, or `None` when the binary is a module
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub core_module: Option<u32>,
+    /// The index of the function, for sources in code
+    pub function: Option<u32>,
+    /// The name of that function, if the binary has a name section
+    pub function_name: Option<String>,
+    /// The WASI calls, operators in the text format, or memories, in the
+    /// order of first use
+    pub uses: Vec<String>,
+}
+
+/// The WASI calls whose results differ between runs
+const WASI_CALLS: &[&str] = &[
+    "clock_time_get",
+    "clock_res_get",
+    "random_get",
+    "poll_oneoff",
+    "sched_yield",
+];
+
+/// The WASI 0.2 interfaces whose functions all return results that differ
+/// between runs
+const WASI_INTERFACES: &[&str] = &["wasi:clocks/", "wasi:random/", "wasi:io/poll"];
+
+/// The operators producing floats that can compute NaN, leaving out the
+/// type they work on. Operators like `f32.abs` only change the sign bit
+/// and keep NaNs as they are.
+const NAN_OPERATORS: &[&str] = &[
+    "Add", "Sub", "Mul", "Div", "Sqrt", "Min", "Max", "Ceil", "Floor", "Trunc", "Nearest",
+    "Demote", "Promote",
+];
+
+/// Find every source of nondeterminism in the module, function by
+/// function
+pub(crate) fn audit(module: &ParsedModule) -> Vec<Nondeterminism> {
+    let names = FunctionNames::new(module);
+    let mut findings = Vec::new();
+
+    let mut imported_memories = 0;
+    for import in &module.imports {
+        if let TypeRef::Memory(ty) = import.ty {
+            imported_memories += 1;
+            if ty.shared {
+                findings.push(shared_memory(format!("{}::{}", import.module, import.name)));
+            }
+        }
+    }
+    for (index, ty) in module.memories.iter().enumerate().skip(imported_memories) {
+        if ty.shared {
+            findings.push(shared_memory(format!("memory {}", index)));
+        }
+    }
+
+    // The imported functions the WASI calls are made through
+    let wasi_calls: Vec<_> = module
+        .imports
+        .iter()
+        .filter(|i| matches!(i.ty, TypeRef::Func(_) | TypeRef::FuncExact(_)))
+        .map(|i| match WasiSnapshot::from_module_name(i.module) {
+            Some(WasiSnapshot::Preview2) => WASI_INTERFACES
+                .iter()
+                .any(|interface| i.module.starts_with(interface))
+                .then(|| format!("{}::{}", i.module, i.name)),
+            Some(_) => WASI_CALLS.contains(&i.name).then(|| i.name.to_owned()),
+            None => None,
+        })
+        .collect();
+
+    for (body, function) in module.bodies.iter().zip(module.imported_functions..) {
+        let mut uses: Vec<(NondeterminismSource, String)> = Vec::new();
+        // A body that fails to decode still contributes the operators read
+        // before the error
+        if let Ok(mut operators) = body.get_operators_reader() {
+            while let Ok(operator) = operators.read() {
+                let used = match &operator {
+                    Operator::Call { function_index } | Operator::ReturnCall { function_index } => {
+                        match wasi_calls.get(*function_index as usize) {
+                            Some(Some(name)) => (NondeterminismSource::WasiCall, name.clone()),
+                            _ => continue,
+                        }
+                    }
+                    operator => {
+                        let source = match proposals::operator_proposal(operator) {
+                            Some(Proposal::RelaxedSimd) => NondeterminismSource::RelaxedSimd,
+                            Some(Proposal::Threads) => NondeterminismSource::Atomics,
+                            _ if produces_nan(operator) => NondeterminismSource::NanFloat,
+                            _ => continue,
+                        };
+                        (source, proposals::instruction_name(operator))
+                    }
+                };
+                uses.push(used);
+            }
+        }
+        for source in [
+            NondeterminismSource::WasiCall,
+            NondeterminismSource::NanFloat,
+            NondeterminismSource::RelaxedSimd,
+            NondeterminismSource::Atomics,
+        ] {
+            let mut found: Vec<String> = Vec::new();
+            for (_, name) in uses.iter().filter(|(s, _)| *s == source) {
+                if !found.contains(name) {
+                    found.push(name.clone());
+                }
+            }
+            if !found.is_empty() {
+                findings.push(Nondeterminism {
+                    source,
+                    core_module: None,
+                    function: Some(function),
+                    function_name: names.find(function).map(String::from),
+                    uses: found,
+                });
+            }
+        }
+    }
+    findings
+}
+
+fn shared_memory(memory: String) -> Nondeterminism {
+    Nondeterminism {
+        source: NondeterminismSource::SharedMemory,
+        core_module: None,
+        function: None,
+        function_name: None,
+        uses: vec![memory],
+    }
+}
+
+/// Whether an operator computes a float, scalar or in a vector, that can
+/// be a NaN
+fn produces_nan(operator: &Operator) -> bool {
+    let name = proposals::operator_name(operator);
+    let operation = ["F32x4", "F64x2", "F32", "F64"]
+        .iter()
+        .find_map(|ty| name.strip_prefix(ty));
+    match operation {
+        Some(operation) => NAN_OPERATORS.iter().any(|o| operation.starts_with(o)),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, analyze_with};
+    use crate::Analyzer;
+
+    fn audit(wat: &str) -> Vec<Nondeterminism> {
+        analyze_with(Analyzer::new().determinism(true), wat)
+            .determinism
+            .unwrap()
+    }
+
+    #[test]
+    fn is_opt_in() {
+        assert_eq!(analyze("(module)").determinism, None);
+    }
+
+    #[test]
+    fn reports_sources_by_function() {
+        let findings = audit(
+            r#"(module
+                (import "wasi_snapshot_preview1" "clock_time_get"
+                    (func $clock (param i32 i64 i32) (result i32)))
+                (import "wasi_snapshot_preview1" "random_get"
+                    (func $random (param i32 i32) (result i32)))
+                (import "env" "memory" (memory 1 1 shared))
+                (memory 1 1 shared)
+                (func $now (result i32)
+                    i32.const 0 i64.const 0 i32.const 0 call $clock
+                    i32.const 0 i32.const 0 call $random
+                    i32.const 0 i32.const 0 call $random
+                    i32.add i32.add)
+                (func $average (param f64 f64) (result f64)
+                    local.get 0 local.get 1 f64.add
+                    f64.const 2 f64.div
+                    f64.abs)
+                (func $swizzle (param v128 v128) (result v128)
+                    local.get 0 local.get 1 i8x16.relaxed_swizzle)
+                (func $count (result i32)
+                    i32.const 0 i32.const 1 i32.atomic.rmw.add))"#,
+        );
+        let found: Vec<_> = findings
+            .iter()
+            .map(|f| {
+                (
+                    f.source,
+                    f.function_name.as_deref().unwrap_or("-"),
+                    f.uses.join(" "),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    NondeterminismSource::SharedMemory,
+                    "-",
+                    "env::memory".to_owned()
+                ),
+                (
+                    NondeterminismSource::SharedMemory,
+                    "-",
+                    "memory 1".to_owned()
+                ),
+                (
+                    NondeterminismSource::WasiCall,
+                    "now",
+                    "clock_time_get random_get".to_owned()
+                ),
+                (
+                    NondeterminismSource::NanFloat,
+                    "average",
+                    "f64.add f64.div".to_owned()
+                ),
+                (
+                    NondeterminismSource::RelaxedSimd,
+                    "swizzle",
+                    "i8x16.relaxed_swizzle".to_owned()
+                ),
+                (
+                    NondeterminismSource::Atomics,
+                    "count",
+                    "i32.atomic.rmw.add".to_owned()
+                ),
+            ]
+        );
+        assert_eq!(findings[2].function, Some(2));
+    }
+
+    #[test]
+    fn plain_wasi_modules_can_be_deterministic() {
+        let findings = audit(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func $write (param i32 i32 i32 i32) (result i32)))
+                (memory 1)
+                (func (export "_start") (param f32) (result f32)
+                    i32.const 1 i32.const 0 i32.const 1 i32.const 0 call $write drop
+                    local.get 0 f32.neg f32.abs))"#,
+        );
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn reports_preview2_interfaces_that_differ_between_runs() {
+        let findings = audit(
+            r#"(module
+                (import "wasi:random/random@0.2.0" "get-random-u64" (func $random (result i64)))
+                (import "wasi:cli/environment@0.2.0" "get-arguments" (func $arguments (param i32)))
+                (func $seed (result i64)
+                    i32.const 0 call $arguments
+                    call $random))"#,
+        );
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].uses,
+            vec!["wasi:random/random@0.2.0::get-random-u64"]
+        );
+    }
+
+    #[test]
+    fn combines_the_core_modules_of_components() {
+        let report = analyze_with(
+            Analyzer::new().determinism(true),
+            r#"(component
+                (core module)
+                (core module
+                    (import "wasi:random/random@0.2.0" "get-random-u64" (func $random (result i64)))
+                    (func $roll (result i64) call $random)
+                    (memory 1 1 shared)))"#,
+        );
+        let found: Vec<_> = report
+            .determinism
+            .iter()
+            .flatten()
+            .map(|f| (f.core_module, f.source, f.function_name.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Some(1), NondeterminismSource::SharedMemory, None),
+                (Some(1), NondeterminismSource::WasiCall, Some("roll")),
+            ]
+        );
+        let mut text = Vec::new();
+        report.write_text(&mut text, false).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("\tshared memory in core module 1: memory 0\n"));
+        assert!(text.contains(
+            "\tnondeterministic WASI calls in function roll of core module 1: wasi:random/random@0.2.0::get-random-u64\n"
+        ));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{Assumptions, Import, WasiSnapshot};
+
+/// How the assumptions of a binary changed between two versions of it
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diff {
+    /// Imports of the new binary the old one did not have
+    pub added_imports: Vec<ChangedImport>,
+    /// Imports of the old binary the new one no longer has
+    pub removed_imports: Vec<ChangedImport>,
+    pub added_categories: Vec<String>,
+    pub removed_categories: Vec<String>,
+    pub added_snapshots: Vec<WasiSnapshot>,
+    pub removed_snapshots: Vec<WasiSnapshot>,
+}
+
+/// An import that was added or removed, along with its category
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChangedImport {
+    pub category: String,
+    pub import: Import,
+}
+
+impl Diff {
+    /// Compare the assumptions of an `old` and a `new` version of a binary
+    pub fn new(old: &Assumptions, new: &Assumptions) -> Diff {
+        let old_imports = old.categorized();
+        let new_imports = new.categorized();
+        let old_categories = categories(&old_imports);
+        let new_categories = categories(&new_imports);
+        Diff {
+            added_imports: missing(&new_imports, &old_imports),
+            removed_imports: missing(&old_imports, &new_imports),
+            added_categories: difference(&new_categories, &old_categories),
+            removed_categories: difference(&old_categories, &new_categories),
+            added_snapshots: difference(&new.wasi.snapshots, &old.wasi.snapshots),
+            removed_snapshots: difference(&old.wasi.snapshots, &new.wasi.snapshots),
+        }
+    }
+
+    /// Whether the two versions make the same assumptions
+    pub fn is_empty(&self) -> bool {
+        self.added_imports.is_empty()
+            && self.removed_imports.is_empty()
+            && self.added_categories.is_empty()
+            && self.removed_categories.is_empty()
+            && self.added_snapshots.is_empty()
+            && self.removed_snapshots.is_empty()
+    }
+}
+
+/// The imports of `imports` that are not in `other`. Imports are the same
+/// when they have the same module and field.
+fn missing(imports: &[(String, &Import)], other: &[(String, &Import)]) -> Vec<ChangedImport> {
+    imports
+        .iter()
+        .filter(|(_, i)| {
+            !other
+                .iter()
+                .any(|(_, o)| o.module == i.module && o.field == i.field)
+        })
+        .map(|(category, import)| ChangedImport {
+            category: category.clone(),
+            import: (*import).clone(),
+        })
+        .collect()
+}
+
+/// The categories used by `imports`, in the order they first appear
+fn categories(imports: &[(String, &Import)]) -> Vec<String> {
+    let mut categories = Vec::new();
+    for (category, _) in imports {
+        if !categories.contains(category) {
+            categories.push(category.clone());
+        }
+    }
+    categories
+}
+
+fn difference<T: Clone + PartialEq>(items: &[T], other: &[T]) -> Vec<T> {
+    items
+        .iter()
+        .filter(|item| !other.contains(item))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::analyze;
+
+    #[test]
+    fn lists_added_and_removed_imports() {
+        let old = analyze(
+            r#"(module
+                (import "wasi_unstable" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+                (import "wasi_unstable" "proc_exit" (func (param i32))))"#,
+        );
+        let new = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "wasi_snapshot_preview1" "sock_send"
+                    (func (param i32 i32 i32 i32 i32) (result i32))))"#,
+        );
+        let diff = Diff::new(&old.assumptions, &new.assumptions);
+        let names = |imports: &[ChangedImport]| {
+            imports
+                .iter()
+                .map(|i| format!("{} {}::{}", i.category, i.import.module, i.import.field))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(&diff.added_imports),
+            vec![
+                "file_system wasi_snapshot_preview1::fd_write",
+                "network wasi_snapshot_preview1::sock_send",
+            ]
+        );
+        assert_eq!(
+            names(&diff.removed_imports),
+            vec![
+                "file_system wasi_unstable::fd_write",
+                "process wasi_unstable::proc_exit",
+            ]
+        );
+        assert_eq!(diff.added_categories, vec!["network"]);
+        assert_eq!(diff.removed_categories, vec!["process"]);
+        assert_eq!(diff.added_snapshots, vec![WasiSnapshot::Preview1]);
+        assert_eq!(diff.removed_snapshots, vec![WasiSnapshot::Unstable]);
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn ignores_order_and_signatures() {
+        let old = analyze(
+            r#"(module
+                (import "env" "a" (func))
+                (import "env" "b" (func)))"#,
+        );
+        let new = analyze(
+            r#"(module
+                (import "env" "b" (func (param i32)))
+                (import "env" "a" (func)))"#,
+        );
+        assert!(Diff::new(&old.assumptions, &new.assumptions).is_empty());
+    }
+}
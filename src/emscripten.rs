@@ -0,0 +1,466 @@
+use crate::{module::ParsedModule, profile::Classifier, Import, Profile};
+use wasmparser::ExternalKind;
+
+/// How an Emscripten binary takes part in dynamic linking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DynamicLinking {
+    /// Built with `-sMAIN_MODULE`, able to load side modules
+    MainModule,
+    /// Built with `-sSIDE_MODULE`, loaded by a main module
+    SideModule,
+}
+
+/// The `env` functions an Emscripten binary expects its JavaScript glue
+/// code to provide, grouped by what they give access to
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EmscriptenAssumptions {
+    /// Whether the binary was built as a main or side module, if it was
+    /// built for dynamic linking
+    pub linking: Option<DynamicLinking>,
+    /// The shared libraries the binary needs to be linked with
+    pub needed_libraries: Vec<String>,
+    /// Whether the binary was found embedded in JavaScript, as done by
+    /// `-sSINGLE_FILE`
+    pub single_file: bool,
+    pub file_system: Vec<Import>,
+    pub network: Vec<Import>,
+    pub environment: Vec<Import>,
+    pub process: Vec<Import>,
+    pub heap: Vec<Import>,
+    pub dynamic_linking: Vec<Import>,
+    pub exception_emulation: Vec<Import>,
+    pub js_interop: Vec<Import>,
+}
+
+/// Import names only the Emscripten JavaScript glue provides
+const SPECIFIC_PREFIXES: &[&str] = &[
+    "emscripten_",
+    "_emscripten_",
+    "__syscall_",
+    "invoke_",
+    "_embind_",
+    "_emval_",
+    "__cxa_find_matching_catch",
+];
+
+/// Exports only Emscripten adds to a binary
+const SPECIFIC_EXPORTS: &[&str] = &[
+    "emscripten_stack_init",
+    "emscripten_stack_get_end",
+    "_emscripten_stack_alloc",
+    "_emscripten_stack_restore",
+    "stackAlloc",
+    "stackSave",
+    "stackRestore",
+];
+
+impl EmscriptenAssumptions {
+    /// Recognize a binary built with Emscripten by its imports and exports
+    pub(crate) fn detect(module: &ParsedModule) -> Option<EmscriptenAssumptions> {
+        let imports = module
+            .imports
+            .iter()
+            .any(|i| i.module == "env" && SPECIFIC_PREFIXES.iter().any(|p| i.name.starts_with(p)));
+        let exports = module
+            .exports
+            .iter()
+            .any(|e| SPECIFIC_EXPORTS.contains(&e.name) || e.name.starts_with("dynCall_"));
+        // Other toolchains (wasi-sdk, Rust) emit `dylink.0` for shared
+        // libraries too, so it is only used to tell main from side modules
+        if !imports && !exports {
+            return None;
+        }
+        let dylink = module
+            .custom_sections
+            .iter()
+            .any(|s| *s == "dylink.0" || *s == "dylink");
+
+        let linking = if dylink {
+            // Only the main module sets up the stack and runs `main`
+            let main = module.exports.iter().any(|e| {
+                e.kind == ExternalKind::Func
+                    && (SPECIFIC_EXPORTS.contains(&e.name)
+                        || ["main", "__main_argc_argv", "_start"].contains(&e.name))
+            });
+            Some(if main {
+                DynamicLinking::MainModule
+            } else {
+                DynamicLinking::SideModule
+            })
+        } else {
+            None
+        };
+        Some(EmscriptenAssumptions {
+            linking,
+            needed_libraries: module
+                .needed_libraries
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+            ..EmscriptenAssumptions::default()
+        })
+    }
+}
+
+impl Classifier for EmscriptenAssumptions {
+    fn classify(&mut self, import: Import) -> Result<(), Import> {
+        let calls = match category(&import.module, &import.field) {
+            Some(Category::FileSystem) => &mut self.file_system,
+            Some(Category::Network) => &mut self.network,
+            Some(Category::Environment) => &mut self.environment,
+            Some(Category::Process) => &mut self.process,
+            Some(Category::Heap) => &mut self.heap,
+            Some(Category::DynamicLinking) => &mut self.dynamic_linking,
+            Some(Category::ExceptionEmulation) => &mut self.exception_emulation,
+            Some(Category::JsInterop) => &mut self.js_interop,
+            None => return Err(import),
+        };
+        calls.push(import);
+        Ok(())
+    }
+}
+
+impl Profile for EmscriptenAssumptions {
+    fn id(&self) -> &'static str {
+        "emscripten"
+    }
+
+    fn name(&self) -> &'static str {
+        "Emscripten"
+    }
+
+    fn categories(&self) -> Vec<(&'static str, &[Import])> {
+        vec![
+            ("file_system", &self.file_system),
+            ("network", &self.network),
+            ("environment", &self.environment),
+            ("process", &self.process),
+            ("heap", &self.heap),
+            ("dynamic_linking", &self.dynamic_linking),
+            ("exception_emulation", &self.exception_emulation),
+            ("js_interop", &self.js_interop),
+        ]
+    }
+
+    fn notes(&self) -> Vec<String> {
+        let mut notes = Vec::new();
+        if self.single_file {
+            notes.push("The binary is embedded in JavaScript, built with -sSINGLE_FILE".to_owned());
+        }
+        match self.linking {
+            Some(DynamicLinking::MainModule) => notes.push(
+                "The binary is a main module built with -sMAIN_MODULE, it can load side modules"
+                    .to_owned(),
+            ),
+            Some(DynamicLinking::SideModule) => notes.push(
+                "The binary is a side module built with -sSIDE_MODULE, it needs a main module to load it"
+                    .to_owned(),
+            ),
+            None => {}
+        }
+        if !self.needed_libraries.is_empty() {
+            notes.push(format!(
+                "The binary needs the shared libraries {}",
+                self.needed_libraries.join(", ")
+            ));
+        }
+        notes
+    }
+}
+
+enum Category {
+    FileSystem,
+    Network,
+    Environment,
+    Process,
+    Heap,
+    DynamicLinking,
+    ExceptionEmulation,
+    JsInterop,
+}
+
+fn category(module: &str, name: &str) -> Option<Category> {
+    // Dynamically linked binaries import the addresses of data and
+    // functions of other modules from the global offset table
+    if module == "GOT.mem" || module == "GOT.func" {
+        return Some(Category::DynamicLinking);
+    }
+    if module != "env" {
+        return None;
+    }
+    let category = match name {
+        "__syscall_socket"
+        | "__syscall_socketpair"
+        | "__syscall_connect"
+        | "__syscall_bind"
+        | "__syscall_listen"
+        | "__syscall_accept4"
+        | "__syscall_sendto"
+        | "__syscall_recvfrom"
+        | "__syscall_sendmsg"
+        | "__syscall_recvmsg"
+        | "__syscall_getsockname"
+        | "__syscall_getpeername"
+        | "__syscall_getsockopt"
+        | "__syscall_setsockopt"
+        | "__syscall_shutdown"
+        | "_emscripten_lookup_name"
+        | "getaddrinfo"
+        | "getnameinfo"
+        | "_getaddrinfo_js" => Category::Network,
+        "__syscall_getpid"
+        | "__syscall_kill"
+        | "__syscall_wait4"
+        | "__syscall_setpgid"
+        | "__syscall_getrusage"
+        | "abort"
+        | "_abort_js"
+        | "__assert_fail"
+        | "exit"
+        | "_exit"
+        | "proc_exit"
+        | "emscripten_force_exit"
+        | "_emscripten_runtime_keepalive_clear" => Category::Process,
+        "emscripten_memcpy_big"
+        | "emscripten_memcpy_js"
+        | "_emscripten_memcpy_js"
+        | "emscripten_resize_heap"
+        | "emscripten_notify_memory_growth"
+        | "emscripten_get_heap_max"
+        | "_emscripten_get_heap_max"
+        | "_mmap_js"
+        | "_munmap_js"
+        | "_msync_js" => Category::Heap,
+        "emscripten_get_now"
+        | "emscripten_get_now_res"
+        | "_emscripten_get_now_is_monotonic"
+        | "emscripten_date_now"
+        | "_emscripten_date_now"
+        | "_tzset_js"
+        | "_localtime_js"
+        | "_gmtime_js"
+        | "_mktime_js"
+        | "_timegm_js"
+        | "getentropy"
+        | "emscripten_get_progname"
+        | "_emscripten_get_progname" => Category::Environment,
+        "dlopen"
+        | "dlsym"
+        | "dlclose"
+        | "_dlopen_js"
+        | "_dlsym_js"
+        | "_dlsym_catchup_js"
+        | "_dlinit"
+        | "_emscripten_dlopen_js"
+        | "__memory_base"
+        | "__table_base"
+        | "__stack_pointer" => Category::DynamicLinking,
+        "__cxa_throw"
+        | "__cxa_begin_catch"
+        | "__cxa_end_catch"
+        | "__cxa_rethrow"
+        | "__cxa_uncaught_exceptions"
+        | "__cxa_current_primary_exception"
+        | "__cxa_rethrow_primary_exception"
+        | "__cxa_increment_exception_refcount"
+        | "__cxa_decrement_exception_refcount"
+        | "__resumeException"
+        | "llvm_eh_typeid_for"
+        | "_emscripten_throw_longjmp"
+        | "emscripten_longjmp"
+        | "saveSetjmp"
+        | "testSetjmp"
+        | "getTempRet0"
+        | "setTempRet0" => Category::ExceptionEmulation,
+        name if name.starts_with("invoke_") || name.starts_with("__cxa_find_matching_catch") => {
+            Category::ExceptionEmulation
+        }
+        name if name.starts_with("__syscall_") => Category::FileSystem,
+        name if name.starts_with("emscripten_asm_const")
+            || name.starts_with("emscripten_run_script")
+            || name.starts_with("emscripten_console_")
+            || name.starts_with("_embind_")
+            || name.starts_with("_emval_")
+            || name.starts_with("emscripten_websocket_")
+            || name == "emscripten_dbg" =>
+        {
+            if name.starts_with("emscripten_websocket_") {
+                Category::Network
+            } else {
+                Category::JsInterop
+            }
+        }
+        _ => return None,
+    };
+    Some(category)
+}
+
+/// Find a wasm binary embedded in JavaScript as a base64 string, like
+/// Emscripten does when building with `-sSINGLE_FILE`
+pub(crate) fn embedded_binary(bytes: &[u8]) -> Option<Vec<u8>> {
+    // "AGFzbQ" is how every base64 encoded wasm binary starts
+    let start = bytes.windows(6).position(|w| w == b"AGFzbQ")?;
+    let mut binary = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &byte in &bytes[start..] {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => break,
+        };
+        buffer = buffer << 6 | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            binary.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(binary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, fields, wasm};
+    use crate::Analyzer;
+
+    /// Encode `bytes` as base64 the way Emscripten embeds a binary
+    fn base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut encoded = String::new();
+        for chunk in bytes.chunks(3) {
+            let buffer = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |b, (i, &byte)| b | u32::from(byte) << (16 - 8 * i));
+            for i in 0..=chunk.len() {
+                encoded.push(ALPHABET[(buffer >> (18 - 6 * i) & 0x3f) as usize] as char);
+            }
+        }
+        while !encoded.len().is_multiple_of(4) {
+            encoded.push('=');
+        }
+        encoded
+    }
+
+    #[test]
+    fn classifies_env_imports() {
+        let report = analyze(
+            r#"(module
+                (import "env" "__syscall_openat" (func))
+                (import "env" "__syscall_connect" (func))
+                (import "env" "emscripten_resize_heap" (func))
+                (import "env" "_tzset_js" (func))
+                (import "env" "exit" (func))
+                (import "env" "invoke_vii" (func))
+                (import "env" "_emval_decref" (func))
+                (import "env" "my_callback" (func)))"#,
+        );
+        let emscripten = report.assumptions.emscripten.as_ref().unwrap();
+        assert_eq!(fields(&emscripten.file_system), vec!["__syscall_openat"]);
+        assert_eq!(fields(&emscripten.network), vec!["__syscall_connect"]);
+        assert_eq!(fields(&emscripten.heap), vec!["emscripten_resize_heap"]);
+        assert_eq!(fields(&emscripten.environment), vec!["_tzset_js"]);
+        assert_eq!(fields(&emscripten.process), vec!["exit"]);
+        assert_eq!(fields(&emscripten.exception_emulation), vec!["invoke_vii"]);
+        assert_eq!(fields(&emscripten.js_interop), vec!["_emval_decref"]);
+        assert_eq!(fields(&report.assumptions.unknown), vec!["my_callback"]);
+        assert_eq!(emscripten.count(), 7);
+        assert_eq!(emscripten.linking, None);
+        assert!(emscripten.notes().is_empty());
+    }
+
+    #[test]
+    fn detects_emscripten_exports() {
+        let report = analyze(r#"(module (func (export "stackSave")))"#);
+        assert!(report.assumptions.emscripten.is_some());
+    }
+
+    #[test]
+    fn plain_wasi_modules_are_not_emscripten() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "env" "exit" (func))
+                (func (export "_start")))"#,
+        );
+        assert!(report.assumptions.emscripten.is_none());
+        assert!(report.assumptions.profiles().is_empty());
+        assert_eq!(fields(&report.assumptions.unknown), vec!["exit"]);
+    }
+
+    #[test]
+    fn reads_dynamic_linking_information() {
+        let side = analyze(
+            r#"(module
+                (@custom "dylink.0" "\02\0b\01\09libfoo.so")
+                (import "GOT.mem" "errno" (global (mut i32)))
+                (import "env" "invoke_vi" (func (param i32 i32)))
+                (func (export "helper")))"#,
+        );
+        let emscripten = side.assumptions.emscripten.as_ref().unwrap();
+        assert_eq!(emscripten.linking, Some(DynamicLinking::SideModule));
+        assert_eq!(emscripten.needed_libraries, vec!["libfoo.so"]);
+        assert_eq!(fields(&emscripten.dynamic_linking), vec!["errno"]);
+        assert_eq!(
+            emscripten.notes(),
+            vec![
+                "The binary is a side module built with -sSIDE_MODULE, it needs a main module to load it",
+                "The binary needs the shared libraries libfoo.so",
+            ]
+        );
+
+        let main = analyze(
+            r#"(module
+                (@custom "dylink.0" "")
+                (func (export "main"))
+                (func (export "stackSave")))"#,
+        );
+        assert_eq!(
+            main.assumptions.emscripten.unwrap().linking,
+            Some(DynamicLinking::MainModule)
+        );
+    }
+
+    #[test]
+    fn shared_libraries_of_other_toolchains_are_not_emscripten() {
+        let report = analyze(
+            r#"(module
+                (@custom "dylink.0" "\02\0b\01\09libfoo.so")
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "GOT.mem" "errno" (global (mut i32)))
+                (func (export "helper")))"#,
+        );
+        assert!(report.assumptions.emscripten.is_none());
+    }
+
+    #[test]
+    fn finds_binaries_embedded_in_javascript() {
+        let binary = wasm(r#"(module (import "env" "__syscall_openat" (func)))"#);
+        let js = format!(
+            "var wasmBinaryFile = 'data:application/octet-stream;base64,{}';\n",
+            base64(&binary)
+        );
+        assert_eq!(embedded_binary(js.as_bytes()), Some(binary));
+        assert_eq!(embedded_binary(b"console.log('no wasm here')"), None);
+
+        let report = Analyzer::new().analyze_bytes(js.as_bytes()).unwrap();
+        let emscripten = report.assumptions.emscripten.unwrap();
+        assert!(emscripten.single_file);
+        assert_eq!(fields(&emscripten.file_system), vec!["__syscall_openat"]);
+        assert_eq!(
+            emscripten.notes(),
+            vec!["The binary is embedded in JavaScript, built with -sSINGLE_FILE"]
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use crate::Proposal;
+use std::{error, fmt, io};
+
+/// The part of a wasm binary an error or finding refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SectionId {
+    /// The magic number and version at the start of the binary
+    Header,
+    Custom,
+    Type,
+    Import,
+    Function,
+    Table,
+    Memory,
+    Global,
+    Export,
+    Start,
+    Element,
+    Code,
+    Data,
+    DataCount,
+    Tag,
+    /// A section of a component, such as the core modules nested in it
+    Component,
+    Unknown(u8),
+}
+
+impl SectionId {
+    pub fn from_id(id: u8) -> SectionId {
+        match id {
+            0 => SectionId::Custom,
+            1 => SectionId::Type,
+            2 => SectionId::Import,
+            3 => SectionId::Function,
+            4 => SectionId::Table,
+            5 => SectionId::Memory,
+            6 => SectionId::Global,
+            7 => SectionId::Export,
+            8 => SectionId::Start,
+            9 => SectionId::Element,
+            10 => SectionId::Code,
+            11 => SectionId::Data,
+            12 => SectionId::DataCount,
+            13 => SectionId::Tag,
+            id => SectionId::Unknown(id),
+        }
+    }
+
+    /// The identifier of the section, as used in the JSON output
+    pub fn name(self) -> &'static str {
+        match self {
+            SectionId::Header => "header",
+            SectionId::Custom => "custom",
+            SectionId::Type => "type",
+            SectionId::Import => "import",
+            SectionId::Function => "function",
+            SectionId::Table => "table",
+            SectionId::Memory => "memory",
+            SectionId::Global => "global",
+            SectionId::Export => "export",
+            SectionId::Start => "start",
+            SectionId::Element => "element",
+            SectionId::Code => "code",
+            SectionId::Data => "data",
+            SectionId::DataCount => "data_count",
+            SectionId::Tag => "tag",
+            SectionId::Component => "component",
+            SectionId::Unknown(_) => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for SectionId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            SectionId::Header => return f.write_str("header"),
+            SectionId::Unknown(id) => return write!(f, "unknown section {}", id),
+            SectionId::Custom => "custom",
+            SectionId::Type => "type",
+            SectionId::Import => "import",
+            SectionId::Function => "function",
+            SectionId::Table => "table",
+            SectionId::Memory => "memory",
+            SectionId::Global => "global",
+            SectionId::Export => "export",
+            SectionId::Start => "start",
+            SectionId::Element => "element",
+            SectionId::Code => "code",
+            SectionId::Data => "data",
+            SectionId::DataCount => "data count",
+            SectionId::Tag => "tag",
+            SectionId::Component => "component",
+        };
+        write!(f, "{} section", name)
+    }
+}
+
+/// Everything that can go wrong while analyzing a wasm binary
+#[derive(Debug)]
+pub enum KontrolleurError {
+    /// The binary could not be read
+    Io(io::Error),
+    /// The binary does not start with the `\0asm` magic number
+    BadMagic,
+    /// The binary is wasm, but of a version kontrolleur does not understand
+    BadVersion { version: u32 },
+    /// A section of the binary could not be decoded
+    MalformedSection {
+        section: SectionId,
+        offset: usize,
+        message: String,
+    },
+    /// The binary uses instructions of a proposal that are newer than the
+    /// ones kontrolleur can parse
+    UnsupportedProposal {
+        proposal: Proposal,
+        section: SectionId,
+        offset: usize,
+    },
+}
+
+impl KontrolleurError {
+    /// The section and byte offset the error was found at, if the error is
+    /// about the contents of the binary
+    pub fn location(&self) -> Option<(SectionId, usize)> {
+        match self {
+            KontrolleurError::Io(_) => None,
+            KontrolleurError::BadMagic => Some((SectionId::Header, 0)),
+            KontrolleurError::BadVersion { .. } => Some((SectionId::Header, 4)),
+            KontrolleurError::MalformedSection {
+                section, offset, ..
+            }
+            | KontrolleurError::UnsupportedProposal {
+                section, offset, ..
+            } => Some((*section, *offset)),
+        }
+    }
+}
+
+impl fmt::Display for KontrolleurError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KontrolleurError::Io(e) => write!(f, "failed to read the binary: {}", e),
+            KontrolleurError::BadMagic => {
+                write!(f, "not a wasm binary, the \\0asm magic number is missing")
+            }
+            KontrolleurError::BadVersion { version } => {
+                write!(f, "unsupported wasm version {}", version)
+            }
+            KontrolleurError::MalformedSection {
+                section,
+                offset,
+                message,
+            } => write!(
+                f,
+                "malformed {} at byte offset {:#x}: {}",
+                section, offset, message
+            ),
+            KontrolleurError::UnsupportedProposal {
+                proposal,
+                section,
+                offset,
+            } => write!(
+                f,
+                "the {} proposal is not supported yet, first used in the {} at byte offset {:#x}",
+                proposal, section, offset
+            ),
+        }
+    }
+}
+
+impl error::Error for KontrolleurError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            KontrolleurError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for KontrolleurError {
+    fn from(e: io::Error) -> KontrolleurError {
+        KontrolleurError::Io(e)
+    }
+}
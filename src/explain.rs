@@ -0,0 +1,183 @@
+use crate::{callgraph::CallGraph, module::ParsedModule, names::FunctionNames, policy, Import};
+use std::collections::VecDeque;
+use wasmparser::TypeRef;
+
+/// Why a binary needs an import: the shortest chains of calls leading to it
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Explanation {
+    /// The position of the core module the import belongs to, among the
+    /// core modules of a component, or `None` when the binary is a module
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub core_module: Option<u32>,
+    pub import: Import,
+    /// One chain for every entry point that can reach the import, shortest
+    /// first
+    pub chains: Vec<CallChain>,
+}
+
+/// The calls leading from an entry point of the binary to an import
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CallChain {
+    /// The name of the export the chain starts at, or `start function`
+    pub entry: String,
+    /// The functions on the chain, starting with the entry point and ending
+    /// with the import
+    pub functions: Vec<String>,
+}
+
+/// Explain every imported function of `module` matching `pattern`, which is
+/// written like an import rule of a policy
+pub(crate) fn explain(module: &ParsedModule, pattern: &str) -> Vec<Explanation> {
+    let graph = CallGraph::new(module);
+    let reachability = graph.import_reachability();
+    let names = FunctionNames::new(module);
+    let functions = module
+        .imports
+        .iter()
+        .filter(|i| matches!(i.ty, TypeRef::Func(_) | TypeRef::FuncExact(_)));
+
+    let mut explanations = Vec::new();
+    for (index, entry) in functions.enumerate() {
+        let mut import = Import::new(module, entry);
+        if !policy::matches_import(pattern, &import) {
+            continue;
+        }
+        import.reachability = Some(reachability[index]);
+        let mut chains: Vec<_> = graph
+            .roots
+            .iter()
+            .filter_map(|(entry, root)| {
+                shortest_path(&graph, *root, index as u32).map(|path| CallChain {
+                    entry: entry.clone(),
+                    functions: path.into_iter().map(|f| names.get(f)).collect(),
+                })
+            })
+            .collect();
+        chains.sort_by_key(|c| c.functions.len());
+        explanations.push(Explanation {
+            core_module: None,
+            import,
+            chains,
+        });
+    }
+    explanations
+}
+
+/// The shortest chain of direct calls from `from` to `to`, both included
+fn shortest_path(graph: &CallGraph, from: u32, to: u32) -> Option<Vec<u32>> {
+    let mut caller = vec![None; graph.callees.len()];
+    let mut queue = VecDeque::new();
+    if from as usize >= caller.len() {
+        return None;
+    }
+    caller[from as usize] = Some(from);
+    queue.push_back(from);
+    while let Some(function) = queue.pop_front() {
+        if function == to {
+            let mut path = vec![to];
+            let mut current = to;
+            while current != from {
+                current = caller[current as usize]?;
+                path.push(current);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &callee in &graph.callees[function as usize] {
+            if let Some(slot @ None) = caller.get_mut(callee as usize) {
+                *slot = Some(function);
+                queue.push_back(callee);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::wasm, Analyzer, Reachability};
+
+    const MODULE: &str = r#"(module
+        (import "wasi_snapshot_preview1" "proc_exit" (func $exit (param i32)))
+        (import "wasi_snapshot_preview1" "sched_yield" (func $yield (result i32)))
+        (func $quit i32.const 1 call $exit)
+        (func $cleanup call $quit)
+        (func $main (export "_start") call $cleanup)
+        (func $abort (export "abort") call $quit))"#;
+
+    #[test]
+    fn lists_the_shortest_chain_of_each_entry_point() {
+        let explanations = Analyzer::new().explain(&wasm(MODULE), "proc_exit").unwrap();
+        assert_eq!(explanations.len(), 1);
+        let explanation = &explanations[0];
+        assert_eq!(explanation.import.field, "proc_exit");
+        assert_eq!(
+            explanation.import.reachability,
+            Some(Reachability::Reachable)
+        );
+        let chains: Vec<_> = explanation
+            .chains
+            .iter()
+            .map(|c| (c.entry.as_str(), c.functions.join(" -> ")))
+            .collect();
+        assert_eq!(
+            chains,
+            vec![
+                (
+                    "abort",
+                    "abort -> quit -> wasi_snapshot_preview1::proc_exit".to_owned()
+                ),
+                (
+                    "_start",
+                    "main -> cleanup -> quit -> wasi_snapshot_preview1::proc_exit".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn explains_unreachable_imports_without_chains() {
+        let explanations = Analyzer::new()
+            .explain(&wasm(MODULE), "wasi_snapshot_preview1::*")
+            .unwrap();
+        assert_eq!(explanations.len(), 2);
+        assert_eq!(explanations[1].import.field, "sched_yield");
+        assert_eq!(
+            explanations[1].import.reachability,
+            Some(Reachability::Unreachable)
+        );
+        assert!(explanations[1].chains.is_empty());
+        assert!(Analyzer::new()
+            .explain(&wasm(MODULE), "env::*")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn explains_the_core_modules_of_components() {
+        let bytes = wasm(
+            r#"(component
+                (core module
+                    (import "host" "log" (func $log))
+                    (func $run (export "run") call $log))
+                (core module
+                    (import "host" "log" (func))
+                    (import "host" "time" (func))))"#,
+        );
+        let explanations = Analyzer::new().explain(&bytes, "host::log").unwrap();
+        let modules: Vec<_> = explanations
+            .iter()
+            .map(|e| (e.core_module, e.chains.len()))
+            .collect();
+        assert_eq!(modules, vec![(Some(0), 1), (Some(1), 0)]);
+
+        let mut text = Vec::new();
+        explanations[0].write_text(&mut text).unwrap();
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            "In core module 0 of the component:\nhost::log is called from:\n\trun: run -> host::log\n"
+        );
+    }
+}
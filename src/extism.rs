@@ -0,0 +1,204 @@
+use crate::{
+    module::ParsedModule, profile::Classifier, ExportMismatch, Import, Profile, Signature,
+    ValueType,
+};
+use wasmparser::ExternalKind;
+
+const ENV: &str = "extism:host/env";
+const USER: &str = "extism:host/user";
+
+/// The host functions an Extism plugin imports, grouped by what they give
+/// access to
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtismAssumptions {
+    /// Exported plugin functions that do not take nothing and return an
+    /// `i32` status, as Extism calls them
+    pub export_mismatches: Vec<ExportMismatch>,
+    pub http: Vec<Import>,
+    pub variables: Vec<Import>,
+    pub config: Vec<Import>,
+    pub logging: Vec<Import>,
+    /// The host functions the application embedding Extism defines
+    pub custom: Vec<Import>,
+    /// The kernel calls that move input, output and errors through Extism's
+    /// memory
+    pub kernel: Vec<Import>,
+    /// Imports of `extism:host/env` kontrolleur does not know about
+    pub unknown: Vec<Import>,
+}
+
+/// Exports the toolchain adds that Extism does not call as plugin functions
+const NOT_PLUGIN_FUNCTIONS: &[&str] = &["_start", "_initialize", "__wasm_call_ctors"];
+
+impl ExtismAssumptions {
+    /// Recognize an Extism plugin by the `extism:host/*` modules it imports
+    /// from, and check that its exports can be called as plugin functions
+    pub(crate) fn detect(module: &ParsedModule) -> Option<ExtismAssumptions> {
+        if !module
+            .imports
+            .iter()
+            .any(|i| i.module == ENV || i.module == USER)
+        {
+            return None;
+        }
+
+        let expected = Signature {
+            params: Vec::new(),
+            results: vec![ValueType::I32],
+        };
+        let export_mismatches = module
+            .exports
+            .iter()
+            .filter(|e| e.kind == ExternalKind::Func && !NOT_PLUGIN_FUNCTIONS.contains(&e.name))
+            .filter_map(|e| {
+                let signature = module.function_signature(e.index)?;
+                (*signature != expected).then(|| ExportMismatch {
+                    export: e.name.to_owned(),
+                    signature: signature.clone(),
+                    expected: expected.clone(),
+                })
+            })
+            .collect();
+        Some(ExtismAssumptions {
+            export_mismatches,
+            ..ExtismAssumptions::default()
+        })
+    }
+
+    pub(crate) fn add(&mut self, import: Import) {
+        if import.module == USER {
+            self.custom.push(import);
+            return;
+        }
+        match import.field.as_str() {
+            "http_request" | "http_status_code" | "http_headers" => self.http.push(import),
+            "var_get" | "var_set" => self.variables.push(import),
+            "config_get" => self.config.push(import),
+            "log_trace" | "log_debug" | "log_info" | "log_warn" | "log_error" | "get_log_level" => {
+                self.logging.push(import)
+            }
+            "input_length" | "input_load_u8" | "input_load_u64" | "input_offset" | "output_set"
+            | "error_set" | "error_get" | "alloc" | "free" | "length" | "length_unsafe"
+            | "load_u8" | "load_u64" | "store_u8" | "store_u64" | "reset" => {
+                self.kernel.push(import)
+            }
+            _ => self.unknown.push(import),
+        }
+    }
+}
+
+impl Classifier for ExtismAssumptions {
+    fn classify(&mut self, import: Import) -> Result<(), Import> {
+        if import.module != ENV && import.module != USER {
+            return Err(import);
+        }
+        self.add(import);
+        Ok(())
+    }
+}
+
+impl Profile for ExtismAssumptions {
+    fn id(&self) -> &'static str {
+        "extism"
+    }
+
+    fn name(&self) -> &'static str {
+        "Extism"
+    }
+
+    fn categories(&self) -> Vec<(&'static str, &[Import])> {
+        vec![
+            ("http", &self.http),
+            ("variables", &self.variables),
+            ("config", &self.config),
+            ("logging", &self.logging),
+            ("custom", &self.custom),
+            ("kernel", &self.kernel),
+        ]
+    }
+
+    fn unknown(&self) -> &[Import] {
+        &self.unknown
+    }
+
+    fn notes(&self) -> Vec<String> {
+        if self.custom.is_empty() {
+            return Vec::new();
+        }
+        vec![format!(
+            "The plugin needs the host to define {} custom function{}",
+            self.custom.len(),
+            if self.custom.len() == 1 { "" } else { "s" }
+        )]
+    }
+
+    fn errors(&self) -> Vec<String> {
+        self.export_mismatches
+            .iter()
+            .map(|m| m.to_string())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, fields};
+
+    #[test]
+    fn sorts_host_functions() {
+        let report = analyze(
+            r#"(module
+                (import "extism:host/env" "http_request" (func (param i64 i64) (result i64)))
+                (import "extism:host/env" "var_get" (func (param i64) (result i64)))
+                (import "extism:host/env" "config_get" (func (param i64) (result i64)))
+                (import "extism:host/env" "log_info" (func (param i64)))
+                (import "extism:host/env" "output_set" (func (param i64 i64)))
+                (import "extism:host/env" "teleport" (func))
+                (import "extism:host/user" "lookup_user" (func (param i64) (result i64)))
+                (func (export "_start"))
+                (func (export "greet") (result i32) i32.const 0))"#,
+        );
+        let extism = report.assumptions.extism.as_ref().unwrap();
+        assert_eq!(fields(&extism.http), vec!["http_request"]);
+        assert_eq!(fields(&extism.variables), vec!["var_get"]);
+        assert_eq!(fields(&extism.config), vec!["config_get"]);
+        assert_eq!(fields(&extism.logging), vec!["log_info"]);
+        assert_eq!(fields(&extism.kernel), vec!["output_set"]);
+        assert_eq!(fields(&extism.custom), vec!["lookup_user"]);
+        assert_eq!(fields(extism.unknown()), vec!["teleport"]);
+        assert_eq!(
+            extism.notes(),
+            vec!["The plugin needs the host to define 1 custom function"]
+        );
+        assert!(extism.errors().is_empty());
+    }
+
+    #[test]
+    fn reports_exports_extism_cannot_call() {
+        let report = analyze(
+            r#"(module
+                (import "extism:host/env" "input_length" (func (result i64)))
+                (func (export "count") (param i32) (result i32) i32.const 0))"#,
+        );
+        let extism = report.assumptions.extism.unwrap();
+        assert_eq!(
+            extism.errors(),
+            vec!["count is exported as (i32) -> i32 but is expected to be () -> i32"]
+        );
+    }
+
+    #[test]
+    fn plain_wasi_modules_are_not_plugins() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "extism" "var_get" (func))
+                (func (export "run") (param i32)))"#,
+        );
+        assert!(report.assumptions.extism.is_none());
+        assert_eq!(fields(&report.assumptions.unknown), vec!["var_get"]);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{module::ParsedModule, profile::Classifier, Import, Profile};
+
+/// The Fastly Compute host calls a service makes, grouped by the platform
+/// resource they give access to
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FastlyAssumptions {
+    /// Sending requests to backends and waiting for their responses
+    pub backend_requests: Vec<Import>,
+    /// Reading and writing the request, response and body handles of the
+    /// service
+    pub http: Vec<Import>,
+    pub kv: Vec<Import>,
+    pub config_store: Vec<Import>,
+    pub secret_store: Vec<Import>,
+    pub logging: Vec<Import>,
+    pub geo: Vec<Import>,
+    pub cache: Vec<Import>,
+    /// Everything else the platform offers, such as device detection, rate
+    /// limiting and ACLs
+    pub platform: Vec<Import>,
+    /// Imports of `fastly_*` modules kontrolleur does not know about
+    pub unknown: Vec<Import>,
+}
+
+impl FastlyAssumptions {
+    /// Recognize a Fastly Compute service by the `fastly_*` modules it
+    /// imports from
+    pub(crate) fn detect(module: &ParsedModule) -> Option<FastlyAssumptions> {
+        if module
+            .imports
+            .iter()
+            .any(|i| i.module.starts_with("fastly_"))
+        {
+            Some(FastlyAssumptions::default())
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn add(&mut self, import: Import) {
+        match import.module.as_str() {
+            // Request handles serve both the client request and the ones
+            // sent to backends
+            "fastly_http_req"
+                if import.field.contains("send")
+                    || import.field.starts_with("pending_req")
+                    || import.field.contains("dynamic_backend") =>
+            {
+                self.backend_requests.push(import)
+            }
+            "fastly_backend" => self.backend_requests.push(import),
+            "fastly_http_req"
+            | "fastly_http_resp"
+            | "fastly_http_body"
+            | "fastly_http_downstream" => self.http.push(import),
+            "fastly_kv_store" | "fastly_object_store" => self.kv.push(import),
+            "fastly_config_store" | "fastly_dictionary" => self.config_store.push(import),
+            "fastly_secret_store" => self.secret_store.push(import),
+            "fastly_log" => self.logging.push(import),
+            "fastly_geo" => self.geo.push(import),
+            "fastly_cache" | "fastly_http_cache" | "fastly_purge" => self.cache.push(import),
+            "fastly_abi"
+            | "fastly_uap"
+            | "fastly_device_detection"
+            | "fastly_erl"
+            | "fastly_acl"
+            | "fastly_async_io"
+            | "fastly_compute_runtime"
+            | "fastly_image_optimizer" => self.platform.push(import),
+            _ => self.unknown.push(import),
+        }
+    }
+}
+
+impl Classifier for FastlyAssumptions {
+    fn classify(&mut self, import: Import) -> Result<(), Import> {
+        if !import.module.starts_with("fastly_") {
+            return Err(import);
+        }
+        self.add(import);
+        Ok(())
+    }
+}
+
+impl Profile for FastlyAssumptions {
+    fn id(&self) -> &'static str {
+        "fastly"
+    }
+
+    fn name(&self) -> &'static str {
+        "Fastly Compute"
+    }
+
+    fn categories(&self) -> Vec<(&'static str, &[Import])> {
+        vec![
+            ("backend_requests", &self.backend_requests),
+            ("http", &self.http),
+            ("kv", &self.kv),
+            ("config_store", &self.config_store),
+            ("secret_store", &self.secret_store),
+            ("logging", &self.logging),
+            ("geo", &self.geo),
+            ("cache", &self.cache),
+            ("platform", &self.platform),
+        ]
+    }
+
+    fn unknown(&self) -> &[Import] {
+        &self.unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, fields};
+
+    #[test]
+    fn sorts_host_calls_by_resource() {
+        let report = analyze(
+            r#"(module
+                (import "fastly_http_req" "send" (func))
+                (import "fastly_http_req" "pending_req_wait" (func))
+                (import "fastly_http_req" "body_downstream_get" (func))
+                (import "fastly_http_resp" "send_downstream" (func))
+                (import "fastly_kv_store" "lookup" (func))
+                (import "fastly_config_store" "get" (func))
+                (import "fastly_secret_store" "plaintext" (func))
+                (import "fastly_log" "write" (func))
+                (import "fastly_geo" "lookup" (func))
+                (import "fastly_cache" "lookup" (func))
+                (import "fastly_erl" "check_rate" (func))
+                (import "fastly_teleport" "beam" (func))
+                (import "wasi_snapshot_preview1" "sched_yield" (func (result i32))))"#,
+        );
+        let fastly = report.assumptions.fastly.as_ref().unwrap();
+        assert_eq!(
+            fields(&fastly.backend_requests),
+            vec!["send", "pending_req_wait"]
+        );
+        // Responses only ever go to the client
+        assert_eq!(
+            fields(&fastly.http),
+            vec!["body_downstream_get", "send_downstream"]
+        );
+        assert_eq!(fields(&fastly.kv), vec!["lookup"]);
+        assert_eq!(fields(&fastly.config_store), vec!["get"]);
+        assert_eq!(fields(&fastly.secret_store), vec!["plaintext"]);
+        assert_eq!(fields(&fastly.logging), vec!["write"]);
+        assert_eq!(fields(&fastly.geo), vec!["lookup"]);
+        assert_eq!(fields(&fastly.cache), vec!["lookup"]);
+        assert_eq!(fields(&fastly.platform), vec!["check_rate"]);
+        assert_eq!(fields(fastly.unknown()), vec!["beam"]);
+        assert_eq!(fastly.count(), 12);
+        assert!(fastly.errors().is_empty());
+        assert_eq!(report.assumptions.wasi.count(), 1);
+    }
+
+    #[test]
+    fn plain_wasi_modules_are_not_fastly_services() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "fastly" "send" (func)))"#,
+        );
+        assert!(report.assumptions.fastly.is_none());
+        assert_eq!(fields(&report.assumptions.unknown), vec!["send"]);
+    }
+}
@@ -0,0 +1,301 @@
+use crate::{module::ParsedModule, profile::Classifier, Import, Profile, WasiSnapshot};
+
+/// The toolchain a Go binary was built with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum GoFlavor {
+    /// The standard toolchain with `GOOS=js GOARCH=wasm`, whose binaries
+    /// need the `wasm_exec.js` shipped with Go
+    Go,
+    /// TinyGo, whose binaries need TinyGo's own `wasm_exec.js` and mix
+    /// WASI calls into the runtime imports
+    TinyGo,
+}
+
+impl GoFlavor {
+    pub fn name(self) -> &'static str {
+        match self {
+            GoFlavor::Go => "Go",
+            GoFlavor::TinyGo => "TinyGo",
+        }
+    }
+}
+
+/// The calls a Go binary makes into its `wasm_exec.js` support code,
+/// grouped by what they give access to
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GoAssumptions {
+    pub flavor: GoFlavor,
+    /// The module the runtime imports come from: `go` before Go 1.21,
+    /// `gojs` since, and `env` for older TinyGo releases and TinyGo binaries
+    /// without runtime imports
+    pub module: String,
+    pub js_interop: Vec<Import>,
+    pub timer: Vec<Import>,
+    /// Calls resetting the views JS keeps of the linear memory after it grows
+    pub memory_view: Vec<Import>,
+    pub io: Vec<Import>,
+    pub process: Vec<Import>,
+    pub random: Vec<Import>,
+    /// Imports of the `go` or `gojs` module kontrolleur does not know about
+    pub unknown: Vec<Import>,
+}
+
+impl GoAssumptions {
+    /// Recognize a Go binary by the modules and names of its runtime
+    /// imports, or a TinyGo binary without them by its exports
+    pub(crate) fn detect(module: &ParsedModule) -> Option<GoAssumptions> {
+        let (flavor, runtime) = match module.imports.iter().find(|i| is_runtime(i.module, i.name)) {
+            Some(runtime) => {
+                // Only TinyGo keeps time in ticks, imports its runtime from
+                // `env` or asks WASI for what the standard toolchain gets
+                // from JS
+                let tiny = runtime.module == "env"
+                    || module.imports.iter().any(|i| {
+                        WasiSnapshot::from_module_name(i.module).is_some()
+                            || i.name == "runtime.ticks"
+                            || i.name == "runtime.sleepTicks"
+                    });
+                let flavor = if tiny { GoFlavor::TinyGo } else { GoFlavor::Go };
+                (flavor, runtime.module)
+            }
+            None if is_tinygo_without_runtime_imports(module) => (GoFlavor::TinyGo, "env"),
+            None => return None,
+        };
+        Some(GoAssumptions {
+            flavor,
+            module: runtime.to_owned(),
+            js_interop: Vec::new(),
+            timer: Vec::new(),
+            memory_view: Vec::new(),
+            io: Vec::new(),
+            process: Vec::new(),
+            random: Vec::new(),
+            unknown: Vec::new(),
+        })
+    }
+
+    pub(crate) fn add(&mut self, import: Import) {
+        match import.field.as_str() {
+            field if field.starts_with("syscall/js.") => self.js_interop.push(import),
+            "runtime.nanotime"
+            | "runtime.nanotime1"
+            | "runtime.walltime"
+            | "runtime.walltime1"
+            | "runtime.scheduleTimeoutEvent"
+            | "runtime.clearTimeoutEvent"
+            | "runtime.ticks"
+            | "runtime.sleepTicks" => self.timer.push(import),
+            "runtime.resetMemoryDataView" => self.memory_view.push(import),
+            "runtime.wasmWrite" | "debug" => self.io.push(import),
+            "runtime.wasmExit" => self.process.push(import),
+            "runtime.getRandomData" => self.random.push(import),
+            _ => self.unknown.push(import),
+        }
+    }
+}
+
+impl Classifier for GoAssumptions {
+    fn classify(&mut self, import: Import) -> Result<(), Import> {
+        if !is_runtime(&import.module, &import.field) {
+            return Err(import);
+        }
+        self.add(import);
+        Ok(())
+    }
+}
+
+impl Profile for GoAssumptions {
+    fn id(&self) -> &'static str {
+        "go"
+    }
+
+    fn name(&self) -> &'static str {
+        self.flavor.name()
+    }
+
+    fn categories(&self) -> Vec<(&'static str, &[Import])> {
+        vec![
+            ("js_interop", &self.js_interop),
+            ("timer", &self.timer),
+            ("memory_view", &self.memory_view),
+            ("io", &self.io),
+            ("process", &self.process),
+            ("random", &self.random),
+        ]
+    }
+
+    fn unknown(&self) -> &[Import] {
+        &self.unknown
+    }
+
+    fn notes(&self) -> Vec<String> {
+        let mut notes = vec![format!(
+            "The binary needs the wasm_exec.js of {}",
+            self.flavor.name()
+        )];
+        if self.flavor == GoFlavor::Go && self.module == "go" {
+            notes.push("Importing from the go module dates the binary before Go 1.21".to_owned());
+        }
+        notes
+    }
+}
+
+/// Whether the import belongs to the Go runtime. Both toolchains name their
+/// imports after the Go function they implement.
+/// Whether the binary looks like one TinyGo built whose program calls
+/// neither into JS nor the timers, leaving only WASI calls and the `env`
+/// imports of `//go:wasmimport`. This is a heuristic: such binaries still
+/// export `_start` along with the asyncify functions of TinyGo's default
+/// scheduler, or the `go_scheduler` and `resume` entry points its
+/// `wasm_exec.js` calls, but other toolchains can export them too.
+fn is_tinygo_without_runtime_imports(module: &ParsedModule) -> bool {
+    let wasi = module
+        .imports
+        .iter()
+        .any(|i| WasiSnapshot::from_module_name(i.module).is_some());
+    let env = module.imports.iter().any(|i| i.module == "env");
+    let exported = |name: &str| module.exports.iter().any(|e| e.name == name);
+    // Emscripten exports the asyncify functions too when built with
+    // `-sASYNCIFY`
+    let emscripten = exported("stackSave") || exported("emscripten_stack_init");
+    let scheduler =
+        exported("asyncify_get_state") || (exported("go_scheduler") && exported("resume"));
+    wasi && env && exported("_start") && scheduler && !emscripten
+}
+
+fn is_runtime(module: &str, name: &str) -> bool {
+    match module {
+        "go" | "gojs" => true,
+        "env" => name.starts_with("runtime.") || name.starts_with("syscall/js."),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, fields};
+
+    #[test]
+    fn detects_the_standard_toolchain() {
+        let report = analyze(
+            r#"(module
+                (import "gojs" "runtime.wasmExit" (func (param i32)))
+                (import "gojs" "runtime.nanotime1" (func (param i32)))
+                (import "gojs" "runtime.getRandomData" (func (param i32)))
+                (import "gojs" "runtime.resetMemoryDataView" (func (param i32)))
+                (import "gojs" "syscall/js.valueGet" (func (param i32)))
+                (import "gojs" "runtime.somethingNew" (func (param i32))))"#,
+        );
+        let go = report.assumptions.go.as_ref().unwrap();
+        assert_eq!(go.flavor, GoFlavor::Go);
+        assert_eq!(go.module, "gojs");
+        assert_eq!(fields(&go.process), vec!["runtime.wasmExit"]);
+        assert_eq!(fields(&go.timer), vec!["runtime.nanotime1"]);
+        assert_eq!(fields(&go.random), vec!["runtime.getRandomData"]);
+        assert_eq!(fields(&go.memory_view), vec!["runtime.resetMemoryDataView"]);
+        assert_eq!(fields(&go.js_interop), vec!["syscall/js.valueGet"]);
+        assert_eq!(fields(go.unknown()), vec!["runtime.somethingNew"]);
+        assert_eq!(go.notes(), vec!["The binary needs the wasm_exec.js of Go"]);
+    }
+
+    #[test]
+    fn dates_binaries_importing_from_go() {
+        let report = analyze(r#"(module (import "go" "runtime.wasmExit" (func (param i32))))"#);
+        let go = report.assumptions.go.unwrap();
+        assert_eq!(go.flavor, GoFlavor::Go);
+        assert_eq!(
+            go.notes()[1],
+            "Importing from the go module dates the binary before Go 1.21"
+        );
+    }
+
+    #[test]
+    fn detects_tinygo() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "gojs" "runtime.ticks" (func (result f64)))
+                (import "gojs" "syscall/js.valueCall" (func)))"#,
+        );
+        let go = report.assumptions.go.as_ref().unwrap();
+        assert_eq!(go.flavor, GoFlavor::TinyGo);
+        assert_eq!(fields(&go.timer), vec!["runtime.ticks"]);
+        assert_eq!(
+            fields(&report.assumptions.wasi.file_system),
+            vec!["fd_write"]
+        );
+
+        let report = analyze(r#"(module (import "env" "syscall/js.valueGet" (func)))"#);
+        assert_eq!(report.assumptions.go.unwrap().flavor, GoFlavor::TinyGo);
+    }
+
+    #[test]
+    fn detects_tinygo_without_runtime_imports() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "env" "add" (func (param i32 i32) (result i32)))
+                (func (export "_start"))
+                (func (export "asyncify_start_unwind") (param i32))
+                (func (export "asyncify_get_state") (result i32)))"#,
+        );
+        let go = report.assumptions.go.as_ref().unwrap();
+        assert_eq!(go.flavor, GoFlavor::TinyGo);
+        assert_eq!(go.module, "env");
+        assert_eq!(go.count(), 0);
+        assert_eq!(fields(&report.assumptions.unknown), vec!["add"]);
+
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "env" "add" (func (param i32 i32) (result i32)))
+                (func (export "_start"))
+                (func (export "go_scheduler"))
+                (func (export "resume")))"#,
+        );
+        assert_eq!(report.assumptions.go.unwrap().flavor, GoFlavor::TinyGo);
+    }
+
+    #[test]
+    fn asyncify_alone_is_not_tinygo() {
+        // Emscripten with -sASYNCIFY
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "env" "emscripten_sleep" (func (param i32)))
+                (func (export "_start"))
+                (func (export "stackSave") (result i32) i32.const 0)
+                (func (export "asyncify_get_state") (result i32) i32.const 0))"#,
+        );
+        assert!(report.assumptions.go.is_none());
+        // No env imports
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (func (export "_start"))
+                (func (export "asyncify_get_state") (result i32) i32.const 0))"#,
+        );
+        assert!(report.assumptions.go.is_none());
+    }
+
+    #[test]
+    fn plain_wasi_modules_are_not_go() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "env" "runtime_ticks" (func)))"#,
+        );
+        assert!(report.assumptions.go.is_none());
+        assert_eq!(fields(&report.assumptions.unknown), vec!["runtime_ticks"]);
+    }
+}
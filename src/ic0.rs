@@ -0,0 +1,443 @@
+use crate::{
+    module::ParsedModule,
+    profile::{self, Classifier, EntryPoint},
+    ExportMismatch, Import, Profile, Signature,
+};
+use wasmparser::ExternalKind;
+
+/// The Internet Computer system API calls of a canister, grouped by what
+/// they give access to
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ic0Assumptions {
+    /// The update methods the canister exports, without the
+    /// `canister_update ` prefix
+    pub updates: Vec<String>,
+    /// The query and composite query methods the canister exports
+    pub queries: Vec<String>,
+    /// Exports starting with `canister_` that name no system method, update
+    /// or query, which the Internet Computer rejects
+    pub invalid_exports: Vec<String>,
+    /// Methods exported as more than one of an update, a query and a
+    /// composite query
+    pub ambiguous_methods: Vec<String>,
+    pub export_mismatches: Vec<ExportMismatch>,
+    /// Reading the arguments and caller of a message and replying to it
+    pub messaging: Vec<Import>,
+    /// Calls to other canisters
+    pub calls: Vec<Import>,
+    pub stable_memory: Vec<Import>,
+    pub certification: Vec<Import>,
+    pub cycles: Vec<Import>,
+    pub time: Vec<Import>,
+    /// What the canister can find out about itself and the subnet it runs
+    /// on
+    pub canister: Vec<Import>,
+    pub debug: Vec<Import>,
+    /// The categories of the calls each method and system method makes
+    pub methods: Vec<EntryPoint>,
+    /// Imports of `ic0` kontrolleur does not know about
+    pub unknown: Vec<Import>,
+}
+
+/// The exports the Internet Computer calls on its own
+const SYSTEM_METHODS: &[&str] = &[
+    "canister_init",
+    "canister_pre_upgrade",
+    "canister_post_upgrade",
+    "canister_inspect_message",
+    "canister_heartbeat",
+    "canister_global_timer",
+    "canister_on_low_wasm_memory",
+];
+
+/// The prefixes of the exports clients call, followed by the name of the
+/// method
+const METHOD_PREFIXES: &[&str] = &[
+    "canister_update ",
+    "canister_query ",
+    "canister_composite_query ",
+];
+
+/// The order the categories of a method are listed in
+const CATEGORIES: &[&str] = &[
+    "messaging",
+    "calls",
+    "stable_memory",
+    "certification",
+    "cycles",
+    "time",
+    "canister",
+    "debug",
+];
+
+impl Ic0Assumptions {
+    /// Recognize a canister by its `ic0` imports or the names of its
+    /// methods, and check the names and types of the methods
+    pub(crate) fn detect(module: &ParsedModule) -> Option<Ic0Assumptions> {
+        let methods = |prefix: &str| -> Vec<String> {
+            module
+                .exports
+                .iter()
+                .filter(|e| e.kind == ExternalKind::Func)
+                .filter_map(|e| e.name.strip_prefix(prefix))
+                .map(String::from)
+                .collect()
+        };
+        let updates = methods("canister_update ");
+        let mut queries = methods("canister_query ");
+        queries.extend(methods("canister_composite_query "));
+        if updates.is_empty()
+            && queries.is_empty()
+            && !module.imports.iter().any(|i| i.module == "ic0")
+        {
+            return None;
+        }
+
+        let mut invalid_exports = Vec::new();
+        let mut export_mismatches = Vec::new();
+        let expected = Signature {
+            params: Vec::new(),
+            results: Vec::new(),
+        };
+        for export in &module.exports {
+            if !export.name.starts_with("canister_") {
+                continue;
+            }
+            let method = export.kind == ExternalKind::Func
+                && (SYSTEM_METHODS.contains(&export.name)
+                    || METHOD_PREFIXES
+                        .iter()
+                        .any(|prefix| export.name.starts_with(prefix)));
+            if !method {
+                invalid_exports.push(export.name.to_owned());
+                continue;
+            }
+            match module.function_signature(export.index) {
+                Some(signature) if *signature != expected => {
+                    export_mismatches.push(ExportMismatch {
+                        export: export.name.to_owned(),
+                        signature: signature.clone(),
+                        expected: expected.clone(),
+                    })
+                }
+                _ => {}
+            }
+        }
+        // Composite queries are among the queries, so a method exported as
+        // both kinds of query shows up twice as well
+        let mut ambiguous_methods = Vec::new();
+        let exported: Vec<_> = updates.iter().chain(&queries).collect();
+        for (position, method) in exported.iter().enumerate() {
+            if exported[..position].contains(method) && !ambiguous_methods.contains(*method) {
+                ambiguous_methods.push(method.to_string());
+            }
+        }
+
+        let methods = profile::entry_points(module, CATEGORIES, |module, name| {
+            category(name).filter(|_| module == "ic0")
+        })
+        .into_iter()
+        .filter(|method| method.export.starts_with("canister_"))
+        .collect();
+        Some(Ic0Assumptions {
+            updates,
+            queries,
+            invalid_exports,
+            ambiguous_methods,
+            export_mismatches,
+            methods,
+            ..Ic0Assumptions::default()
+        })
+    }
+
+    pub(crate) fn add(&mut self, import: Import) {
+        let calls = match category(&import.field) {
+            Some("messaging") => &mut self.messaging,
+            Some("calls") => &mut self.calls,
+            Some("stable_memory") => &mut self.stable_memory,
+            Some("certification") => &mut self.certification,
+            Some("cycles") => &mut self.cycles,
+            Some("time") => &mut self.time,
+            Some("canister") => &mut self.canister,
+            Some("debug") => &mut self.debug,
+            _ => &mut self.unknown,
+        };
+        calls.push(import);
+    }
+}
+
+impl Classifier for Ic0Assumptions {
+    fn classify(&mut self, import: Import) -> Result<(), Import> {
+        if import.module != "ic0" {
+            return Err(import);
+        }
+        self.add(import);
+        Ok(())
+    }
+}
+
+impl Profile for Ic0Assumptions {
+    fn id(&self) -> &'static str {
+        "ic0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Internet Computer"
+    }
+
+    fn categories(&self) -> Vec<(&'static str, &[Import])> {
+        vec![
+            ("messaging", &self.messaging),
+            ("calls", &self.calls),
+            ("stable_memory", &self.stable_memory),
+            ("certification", &self.certification),
+            ("cycles", &self.cycles),
+            ("time", &self.time),
+            ("canister", &self.canister),
+            ("debug", &self.debug),
+        ]
+    }
+
+    fn unknown(&self) -> &[Import] {
+        &self.unknown
+    }
+
+    fn notes(&self) -> Vec<String> {
+        let methods = |count: usize, kind: &str| {
+            format!(
+                "{} {} method{}",
+                count,
+                kind,
+                if count == 1 { "" } else { "s" }
+            )
+        };
+        vec![format!(
+            "The canister exports {} and {}",
+            methods(self.updates.len(), "update"),
+            methods(self.queries.len(), "query")
+        )]
+    }
+
+    fn errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for export in &self.invalid_exports {
+            errors.push(format!(
+                "{} is not a system method, update or query but uses the canister_ prefix",
+                export
+            ));
+        }
+        for method in &self.ambiguous_methods {
+            errors.push(format!(
+                "{} is exported as more than one of an update, a query and a composite query",
+                method
+            ));
+        }
+        errors.extend(self.export_mismatches.iter().map(|m| m.to_string()));
+        errors
+    }
+
+    fn entry_points(&self) -> &[EntryPoint] {
+        &self.methods
+    }
+}
+
+/// The category of a system API call
+fn category(name: &str) -> Option<&'static str> {
+    let category = match name {
+        name if name.starts_with("msg_cycles_")
+            || name.starts_with("call_cycles_add")
+            || name.starts_with("canister_cycle_balance")
+            || name.starts_with("canister_liquid_cycle_balance")
+            || name.starts_with("cost_")
+            || name == "mint_cycles"
+            || name == "mint_cycles128"
+            || name == "cycles_burn128" =>
+        {
+            "cycles"
+        }
+        name if name.starts_with("msg_") || name == "accept_message" => "messaging",
+        name if name.starts_with("call_") => "calls",
+        name if name.starts_with("stable_") || name.starts_with("stable64_") => "stable_memory",
+        "certified_data_set"
+        | "data_certificate_present"
+        | "data_certificate_size"
+        | "data_certificate_copy" => "certification",
+        "time" | "global_timer_set" | "performance_counter" => "time",
+        name if name.starts_with("canister_")
+            || name.starts_with("subnet_self_")
+            || name.starts_with("root_key_")
+            || name == "is_controller"
+            || name == "in_replicated_execution" =>
+        {
+            "canister"
+        }
+        "debug_print" | "trap" => "debug",
+        _ => return None,
+    };
+    Some(category)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, fields};
+
+    const CANISTER: &str = r#"(module
+        (import "ic0" "msg_reply" (func $reply))
+        (import "ic0" "msg_cycles_accept128" (func (param i64 i64 i32)))
+        (import "ic0" "call_new" (func (param i32 i32 i32 i32 i32 i32 i32 i32)))
+        (import "ic0" "stable64_read" (func (param i64 i64 i64)))
+        (import "ic0" "certified_data_set" (func $certify (param i32 i32)))
+        (import "ic0" "time" (func $time (result i64)))
+        (import "ic0" "canister_self_size" (func (result i32)))
+        (import "ic0" "debug_print" (func (param i32 i32)))
+        (import "ic0" "teleport" (func))
+        (func (export "canister_init")
+            i32.const 0 i32.const 0 call $certify)
+        (func (export "canister_update greet")
+            call $time drop
+            call $reply)
+        (func (export "canister_query name") call $reply))"#;
+
+    #[test]
+    fn sorts_system_api_calls() {
+        let report = analyze(CANISTER);
+        let ic0 = report.assumptions.ic0.as_ref().unwrap();
+        assert_eq!(fields(&ic0.messaging), vec!["msg_reply"]);
+        assert_eq!(fields(&ic0.cycles), vec!["msg_cycles_accept128"]);
+        assert_eq!(fields(&ic0.calls), vec!["call_new"]);
+        assert_eq!(fields(&ic0.stable_memory), vec!["stable64_read"]);
+        assert_eq!(fields(&ic0.certification), vec!["certified_data_set"]);
+        assert_eq!(fields(&ic0.time), vec!["time"]);
+        assert_eq!(fields(&ic0.canister), vec!["canister_self_size"]);
+        assert_eq!(fields(&ic0.debug), vec!["debug_print"]);
+        assert_eq!(fields(ic0.unknown()), vec!["teleport"]);
+        assert_eq!(ic0.updates, vec!["greet"]);
+        assert_eq!(ic0.queries, vec!["name"]);
+        assert!(ic0.errors().is_empty());
+        assert_eq!(
+            ic0.notes(),
+            vec!["The canister exports 1 update method and 1 query method"]
+        );
+    }
+
+    #[test]
+    fn lists_the_categories_each_method_uses() {
+        let report = analyze(CANISTER);
+        let methods: Vec<_> = report
+            .assumptions
+            .ic0
+            .unwrap()
+            .methods
+            .into_iter()
+            .map(|m| (m.export, m.categories.join(" ")))
+            .collect();
+        assert_eq!(
+            methods,
+            vec![
+                ("canister_init".to_owned(), "certification".to_owned()),
+                (
+                    "canister_update greet".to_owned(),
+                    "messaging time".to_owned()
+                ),
+                ("canister_query name".to_owned(), "messaging".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_categories_only_reached_through_call_indirect() {
+        let report = analyze(
+            r#"(module
+                (import "ic0" "msg_reply" (func $reply))
+                (import "ic0" "time" (func $time (result i64)))
+                (type $callback (func))
+                (table 1 funcref)
+                (elem (i32.const 0) $now)
+                (func $now call $time drop)
+                (func (export "canister_update tick")
+                    call $reply
+                    i32.const 0
+                    call_indirect (type $callback))
+                (func (export "canister_query name") call $reply))"#,
+        );
+        let methods: Vec<_> = report
+            .assumptions
+            .ic0
+            .unwrap()
+            .methods
+            .into_iter()
+            .map(|m| (m.export, m.categories, m.indirect_categories))
+            .collect();
+        assert_eq!(
+            methods,
+            vec![
+                (
+                    "canister_update tick".to_owned(),
+                    vec!["messaging".to_owned()],
+                    vec!["time".to_owned()]
+                ),
+                (
+                    "canister_query name".to_owned(),
+                    vec!["messaging".to_owned()],
+                    Vec::new()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_methods_the_internet_computer_rejects() {
+        let report = analyze(
+            r#"(module
+                (func (export "canister_update greet"))
+                (func (export "canister_query greet"))
+                (func (export "canister_update add") (param i32) (result i32) local.get 0)
+                (func (export "canister_upgrade")))"#,
+        );
+        let ic0 = report.assumptions.ic0.unwrap();
+        assert_eq!(
+            ic0.errors(),
+            vec![
+                "canister_upgrade is not a system method, update or query but uses the canister_ prefix",
+                "greet is exported as more than one of an update, a query and a composite query",
+                "canister_update add is exported as (i32) -> i32 but is expected to be () -> ()",
+            ]
+        );
+        assert_eq!(
+            ic0.notes(),
+            vec!["The canister exports 2 update methods and 1 query method"]
+        );
+    }
+
+    #[test]
+    fn queries_cannot_also_be_composite_queries() {
+        let report = analyze(
+            r#"(module
+                (func (export "canister_query total"))
+                (func (export "canister_composite_query total"))
+                (func (export "canister_composite_query sum")))"#,
+        );
+        let ic0 = report.assumptions.ic0.unwrap();
+        assert_eq!(ic0.queries, vec!["total", "total", "sum"]);
+        assert_eq!(ic0.ambiguous_methods, vec!["total"]);
+        assert_eq!(
+            ic0.errors(),
+            vec!["total is exported as more than one of an update, a query and a composite query"]
+        );
+    }
+
+    #[test]
+    fn plain_wasi_modules_are_not_canisters() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "env" "msg_reply" (func))
+                (func (export "canister_like")))"#,
+        );
+        assert!(report.assumptions.ic0.is_none());
+        assert_eq!(fields(&report.assumptions.unknown), vec!["msg_reply"]);
+    }
+}
@@ -0,0 +1,532 @@
+//! Machine readable output following `schema/report.schema.json` and, for
+//! `kontrolleur diff`, `schema/diff.schema.json`.
+//!
+//! The types in this module are the serialized form of the report and are
+//! kept separate from the report types so that the schema only changes
+//! when `SCHEMA_VERSION` is bumped.
+
+use crate::{
+    ChangedImport, ComponentAssumptions, ComponentItem, CoreModule, Diff, EntryPoint, Import,
+    ImportLink, ImportSource, Limits, Nondeterminism, Profile, ProposalUse, Report, Signature,
+    ValueType,
+};
+use serde::Serialize;
+
+/// The version of the JSON schemas. Bump this whenever a field is removed or
+/// changes meaning.
+pub const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Serialize)]
+struct Document<'a> {
+    schema_version: u32,
+    total: usize,
+    wasi: Option<Wasi<'a>>,
+    profiles: Vec<ProfileEntry<'a>>,
+    imports: Vec<Entry<'a>>,
+    memories: Vec<Memory<'a>>,
+    tables: Vec<Table<'a>>,
+    proposals: Vec<ProposalEntry<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    determinism: Option<Vec<NondeterminismEntry<'a>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    component: Option<Component<'a>>,
+}
+
+#[derive(Serialize)]
+struct Component<'a> {
+    imports: Vec<ComponentEntry<'a>>,
+    exports: Vec<ComponentEntry<'a>>,
+    modules: usize,
+    components: usize,
+    core_modules: Vec<CoreModuleEntry<'a>>,
+}
+
+impl<'a> Component<'a> {
+    fn new(component: &'a ComponentAssumptions) -> Component<'a> {
+        Component {
+            imports: component.imports.iter().map(ComponentEntry::new).collect(),
+            exports: component.exports.iter().map(ComponentEntry::new).collect(),
+            modules: component.modules,
+            components: component.components,
+            core_modules: component
+                .core_modules
+                .iter()
+                .map(CoreModuleEntry::new)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct CoreModuleEntry<'a> {
+    index: u32,
+    name: Option<&'a str>,
+    offset: usize,
+    adapter: bool,
+    report: Document<'a>,
+    links: Vec<LinkEntry<'a>>,
+}
+
+impl<'a> CoreModuleEntry<'a> {
+    fn new(module: &'a CoreModule) -> CoreModuleEntry<'a> {
+        CoreModuleEntry {
+            index: module.index,
+            name: module.name.as_deref(),
+            offset: module.offset,
+            adapter: module.adapter,
+            report: document(&module.report),
+            links: module.links.iter().map(LinkEntry::new).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct LinkEntry<'a> {
+    module: &'a str,
+    field: &'a str,
+    source: SourceEntry<'a>,
+    reaches: &'a [String],
+}
+
+impl<'a> LinkEntry<'a> {
+    fn new(link: &'a ImportLink) -> LinkEntry<'a> {
+        let source = match &link.source {
+            ImportSource::Module { module, export } => SourceEntry::Module {
+                module: *module,
+                export,
+            },
+            ImportSource::Adapter { module, export } => SourceEntry::Adapter {
+                module: *module,
+                export,
+            },
+            ImportSource::ComponentImport { import, function } => SourceEntry::ComponentImport {
+                import,
+                function: function.as_deref(),
+            },
+            ImportSource::Builtin { name } => SourceEntry::Builtin { name },
+            ImportSource::Unresolved => SourceEntry::Unresolved,
+        };
+        LinkEntry {
+            module: &link.module,
+            field: &link.field,
+            source,
+            reaches: &link.reaches,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum SourceEntry<'a> {
+    Module {
+        module: u32,
+        export: &'a str,
+    },
+    Adapter {
+        module: u32,
+        export: &'a str,
+    },
+    ComponentImport {
+        import: &'a str,
+        function: Option<&'a str>,
+    },
+    Builtin {
+        name: &'a str,
+    },
+    Unresolved,
+}
+
+#[derive(Serialize)]
+struct ComponentEntry<'a> {
+    name: &'a str,
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<&'a str>,
+    functions: &'a [String],
+}
+
+impl<'a> ComponentEntry<'a> {
+    fn new(item: &'a ComponentItem) -> ComponentEntry<'a> {
+        ComponentEntry {
+            name: &item.name,
+            kind: item.kind.name(),
+            category: item.category.as_deref(),
+            functions: &item.functions,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Memory<'a> {
+    module: &'a str,
+    field: &'a str,
+    limits: &'a Limits,
+    shared: bool,
+    memory64: bool,
+}
+
+#[derive(Serialize)]
+struct Table<'a> {
+    module: &'a str,
+    field: &'a str,
+    element_type: ValueType,
+    limits: &'a Limits,
+}
+
+#[derive(Serialize)]
+struct ProposalEntry<'a> {
+    proposal: &'static str,
+    section: &'static str,
+    offset: usize,
+    function: Option<u32>,
+    function_name: Option<&'a str>,
+}
+
+impl<'a> ProposalEntry<'a> {
+    fn new(proposal: &'a ProposalUse) -> ProposalEntry<'a> {
+        ProposalEntry {
+            proposal: proposal.proposal.name(),
+            section: proposal.section.name(),
+            offset: proposal.offset,
+            function: proposal.function,
+            function_name: proposal.function_name.as_deref(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct NondeterminismEntry<'a> {
+    source: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    core_module: Option<u32>,
+    function: Option<u32>,
+    function_name: Option<&'a str>,
+    uses: &'a [String],
+}
+
+impl<'a> NondeterminismEntry<'a> {
+    fn new(finding: &'a Nondeterminism) -> NondeterminismEntry<'a> {
+        NondeterminismEntry {
+            source: finding.source.name(),
+            core_module: finding.core_module,
+            function: finding.function,
+            function_name: finding.function_name.as_deref(),
+            uses: &finding.uses,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Wasi<'a> {
+    snapshots: Vec<&'static str>,
+    abi_differences: Vec<&'static str>,
+    categories: Vec<&'static str>,
+    signature_mismatches: Vec<Mismatch<'a>>,
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct ProfileEntry<'a> {
+    id: &'static str,
+    name: &'static str,
+    categories: Vec<&'static str>,
+    notes: Vec<String>,
+    errors: Vec<String>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    entry_points: &'a [EntryPoint],
+    count: usize,
+}
+
+impl<'a> ProfileEntry<'a> {
+    fn new(profile: &'a dyn Profile) -> ProfileEntry<'a> {
+        ProfileEntry {
+            id: profile.id(),
+            name: profile.name(),
+            categories: profile
+                .categories()
+                .iter()
+                .filter(|(_, imports)| !imports.is_empty())
+                .map(|(category, _)| *category)
+                .collect(),
+            notes: profile.notes(),
+            errors: profile.errors(),
+            entry_points: profile.entry_points(),
+            count: profile.count(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Mismatch<'a> {
+    snapshot: &'static str,
+    field: &'a str,
+    kind: &'static str,
+    signature: Option<&'a Signature>,
+    expected: &'a Signature,
+}
+
+#[derive(Serialize)]
+struct DiffDocument<'a> {
+    schema_version: u32,
+    added_imports: Vec<Entry<'a>>,
+    removed_imports: Vec<Entry<'a>>,
+    added_categories: &'a [String],
+    removed_categories: &'a [String],
+    added_snapshots: Vec<&'static str>,
+    removed_snapshots: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+struct Entry<'a> {
+    module: &'a str,
+    field: &'a str,
+    kind: &'static str,
+    signature: Option<&'a Signature>,
+    category: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reachability: Option<&'static str>,
+}
+
+impl<'a> Entry<'a> {
+    fn new(import: &'a Import, category: String) -> Entry<'a> {
+        Entry {
+            module: &import.module,
+            field: &import.field,
+            kind: import.kind.name(),
+            signature: import.signature.as_ref(),
+            category,
+            reachability: import.reachability.map(|r| r.name()),
+        }
+    }
+}
+
+fn document(report: &Report) -> Document<'_> {
+    let assumptions = &report.assumptions;
+    let wasi = &assumptions.wasi;
+    let imports = assumptions
+        .categorized()
+        .into_iter()
+        .map(|(category, import)| Entry::new(import, category))
+        .collect();
+
+    let wasi = if wasi.count() > 0 {
+        Some(Wasi {
+            snapshots: wasi.snapshots.iter().map(|s| s.module_name()).collect(),
+            abi_differences: wasi
+                .abi_differences
+                .iter()
+                .map(|d| d.description())
+                .collect(),
+            categories: wasi
+                .categories()
+                .iter()
+                .filter(|(_, imports)| !imports.is_empty())
+                .map(|(category, _)| *category)
+                .collect(),
+            signature_mismatches: wasi
+                .signature_mismatches
+                .iter()
+                .map(|m| Mismatch {
+                    snapshot: m.snapshot.module_name(),
+                    field: &m.import.field,
+                    kind: m.import.kind.name(),
+                    signature: m.import.signature.as_ref(),
+                    expected: &m.expected,
+                })
+                .collect(),
+            count: wasi.count(),
+        })
+    } else {
+        None
+    };
+
+    Document {
+        schema_version: SCHEMA_VERSION,
+        total: assumptions.count(),
+        wasi,
+        profiles: assumptions
+            .profiles()
+            .into_iter()
+            .map(ProfileEntry::new)
+            .collect(),
+        imports,
+        memories: assumptions
+            .memories
+            .iter()
+            .map(|m| Memory {
+                module: &m.import.module,
+                field: &m.import.field,
+                limits: &m.limits,
+                shared: m.shared,
+                memory64: m.memory64,
+            })
+            .collect(),
+        tables: assumptions
+            .tables
+            .iter()
+            .map(|t| Table {
+                module: &t.import.module,
+                field: &t.import.field,
+                element_type: t.element_type,
+                limits: &t.limits,
+            })
+            .collect(),
+        proposals: report.proposals.iter().map(ProposalEntry::new).collect(),
+        determinism: report
+            .determinism
+            .as_ref()
+            .map(|findings| findings.iter().map(NondeterminismEntry::new).collect()),
+        component: assumptions.component.as_ref().map(Component::new),
+    }
+}
+
+/// Render the report as a pretty printed JSON document
+pub fn to_string(report: &Report) -> String {
+    serde_json::to_string_pretty(&document(report)).expect("report is always serializable")
+}
+
+fn changed_entries(changes: &[ChangedImport]) -> Vec<Entry<'_>> {
+    changes
+        .iter()
+        .map(|c| Entry::new(&c.import, c.category.clone()))
+        .collect()
+}
+
+fn diff_document(diff: &Diff) -> DiffDocument<'_> {
+    DiffDocument {
+        schema_version: SCHEMA_VERSION,
+        added_imports: changed_entries(&diff.added_imports),
+        removed_imports: changed_entries(&diff.removed_imports),
+        added_categories: &diff.added_categories,
+        removed_categories: &diff.removed_categories,
+        added_snapshots: diff
+            .added_snapshots
+            .iter()
+            .map(|s| s.module_name())
+            .collect(),
+        removed_snapshots: diff
+            .removed_snapshots
+            .iter()
+            .map(|s| s.module_name())
+            .collect(),
+    }
+}
+
+/// Render the differences between two binaries as a pretty printed JSON
+/// document
+pub fn diff_to_string(diff: &Diff) -> String {
+    serde_json::to_string_pretty(&diff_document(diff)).expect("diff is always serializable")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, analyze_with};
+    use crate::Analyzer;
+
+    const REPORT_SCHEMA: &str = include_str!("../schema/report.schema.json");
+    const DIFF_SCHEMA: &str = include_str!("../schema/diff.schema.json");
+
+    /// Check `json` against `schema`, which may refer to the report schema
+    fn validate(json: &str, schema: &str) {
+        let parse = |json: &str| serde_json::from_str::<serde_json::Value>(json).unwrap();
+        let report = jsonschema::Resource::from_contents(parse(REPORT_SCHEMA));
+        let validator = jsonschema::options()
+            .with_base_uri("file:///schema/")
+            .with_resource("file:///schema/report.schema.json", report)
+            .build(&parse(schema))
+            .unwrap();
+        let json = parse(json);
+        let errors: Vec<_> = validator
+            .iter_errors(&json)
+            .map(|e| format!("{} at {}", e, e.instance_path()))
+            .collect();
+        assert!(errors.is_empty(), "{:#?}", errors);
+    }
+
+    #[test]
+    fn reports_follow_the_schema() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_unstable" "fd_seek"
+                    (func (param i32 i64 i32 i32) (result i32)))
+                (import "wasi_unstable" "fd_close" (func (param i64) (result i32)))
+                (import "env" "memory" (memory 1)))"#,
+        );
+        let json = to_string(&report);
+        validate(&json, REPORT_SCHEMA);
+        let document: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(document["schema_version"], SCHEMA_VERSION);
+        assert_eq!(document["imports"][0]["category"], "file_system");
+        assert_eq!(document["imports"][2]["category"], "memory");
+    }
+
+    #[test]
+    fn schemas_carry_the_schema_version() {
+        for schema in &[REPORT_SCHEMA, DIFF_SCHEMA] {
+            let schema: serde_json::Value = serde_json::from_str(schema).unwrap();
+            assert_eq!(
+                schema["properties"]["schema_version"]["const"],
+                SCHEMA_VERSION
+            );
+        }
+        let old = analyze("(module)");
+        let json = diff_to_string(&Diff::new(&old.assumptions, &old.assumptions));
+        let document: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(document["schema_version"], SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn wasi_is_null_without_wasi_imports() {
+        let json = to_string(&analyze("(module)"));
+        validate(&json, REPORT_SCHEMA);
+        assert!(json.contains("\"wasi\": null"));
+    }
+
+    #[test]
+    fn diffs_follow_the_schema() {
+        let old = analyze(r#"(module (import "wasi_unstable" "proc_exit" (func (param i32))))"#);
+        let new = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "sock_send"
+                    (func (param i32 i32 i32 i32 i32) (result i32))))"#,
+        );
+        let json = diff_to_string(&Diff::new(&old.assumptions, &new.assumptions));
+        validate(&json, DIFF_SCHEMA);
+        let document: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(document["added_categories"][0], "network");
+        assert_eq!(document["removed_snapshots"][0], "wasi_unstable");
+    }
+
+    #[test]
+    fn determinism_follows_the_schema() {
+        let report = analyze_with(
+            Analyzer::new().determinism(true),
+            r#"(module
+                (import "wasi_snapshot_preview1" "random_get"
+                    (func $random (param i32 i32) (result i32)))
+                (memory 1 1 shared)
+                (func $roll (result i32) i32.const 0 i32.const 4 call $random))"#,
+        );
+        let json = to_string(&report);
+        validate(&json, REPORT_SCHEMA);
+        let document: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(document["determinism"][1]["function_name"], "roll");
+    }
+
+    #[test]
+    fn components_follow_the_schema() {
+        let report = analyze_with(
+            Analyzer::new().determinism(true),
+            r#"(component
+                (import "wasi:cli/environment@0.2.0" (instance $env
+                    (export "get-arguments" (func))))
+                (core module (memory 1 1 shared))
+                (export "env" (instance $env)))"#,
+        );
+        let json = to_string(&report);
+        validate(&json, REPORT_SCHEMA);
+        let document: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(document["determinism"][0]["core_module"], 0);
+    }
+}
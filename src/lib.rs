@@ -0,0 +1,434 @@
+//! Inspect a wasm binary to see what it expects from its environment.
+//!
+//! The entry point is [`Analyzer`], which turns a wasm binary into a
+//! [`Report`] describing the imports the binary relies on. Imports following
+//! the conventions of a known toolchain, such as Emscripten or
+//! wasm-bindgen, are sorted by a
+//! [`Profile`]:
+//!
+//! ```no_run
+//! let bytes = std::fs::read("module.wasm").unwrap();
+//! let report = kontrolleur::Analyzer::new().analyze_bytes(&bytes).unwrap();
+//! println!("{} imports", report.assumptions.count());
+//! ```
+//!
+//! With the `serde` feature (enabled by default) all report types implement
+//! `Serialize` and `Deserialize`, and the [`json`] module produces the
+//! versioned document printed by `kontrolleur --format json`.
+//!
+//! A [`Diff`] compares the assumptions of two versions of a binary, as done
+//! by `kontrolleur diff old.wasm new.wasm`, and a [`Policy`] checks a report
+//! against allow and deny rules for categories and import names, as done by
+//! `kontrolleur --policy kontrolleur.toml`.
+
+mod assemblyscript;
+mod assumptions;
+mod callgraph;
+mod component;
+mod cosmwasm;
+mod determinism;
+mod diff;
+mod emscripten;
+mod error;
+mod explain;
+mod extism;
+mod fastly;
+mod go;
+mod ic0;
+#[cfg(feature = "serde")]
+pub mod json;
+mod module;
+mod names;
+mod near;
+mod parse;
+mod policy;
+mod profile;
+mod proposals;
+mod proxy_wasm;
+mod report;
+mod seal;
+mod stylus;
+mod text;
+mod wasi;
+mod wasm_bindgen;
+
+pub use crate::assemblyscript::{AssemblyScriptAssumptions, AssemblyScriptRuntime};
+pub use crate::assumptions::{
+    Assumptions, Import, ImportKind, Limits, MemoryImport, Signature, TableImport, ValueType,
+};
+pub use crate::callgraph::Reachability;
+pub use crate::component::{
+    ComponentAssumptions, ComponentItem, ComponentItemKind, CoreModule, ImportLink, ImportSource,
+};
+pub use crate::cosmwasm::{CosmWasmAssumptions, FloatOperator, LATEST_INTERFACE_VERSION};
+pub use crate::determinism::{Nondeterminism, NondeterminismSource};
+pub use crate::diff::{ChangedImport, Diff};
+pub use crate::emscripten::{DynamicLinking, EmscriptenAssumptions};
+pub use crate::error::{KontrolleurError, SectionId};
+pub use crate::explain::{CallChain, Explanation};
+pub use crate::extism::ExtismAssumptions;
+pub use crate::fastly::FastlyAssumptions;
+pub use crate::go::{GoAssumptions, GoFlavor};
+pub use crate::ic0::Ic0Assumptions;
+pub use crate::near::NearAssumptions;
+pub use crate::policy::{Policy, PolicyError, Rule, Rules, Violation};
+pub use crate::profile::{EntryPoint, ExportMismatch, Profile};
+pub use crate::proposals::{Proposal, ProposalUse};
+pub use crate::proxy_wasm::{ProxyWasmAbi, ProxyWasmAssumptions};
+pub use crate::report::Report;
+pub use crate::seal::{SealAssumptions, MAX_MEMORY_PAGES};
+pub use crate::stylus::StylusAssumptions;
+pub use crate::wasi::{AbiDifference, SignatureMismatch, WasiAssumptions, WasiSnapshot};
+pub use crate::wasm_bindgen::WasmBindgenAssumptions;
+
+use crate::{callgraph::CallGraph, module::ParsedModule, names::FunctionNames};
+use parity_wasm::elements::Module;
+use std::{fs, path::Path};
+use wasmparser::TypeRef;
+
+/// Analyzes wasm binaries for the assumptions they make about their
+/// environment.
+#[derive(Debug, Default)]
+pub struct Analyzer {
+    reachability: bool,
+    cosmwasm: Option<u32>,
+    determinism: bool,
+}
+
+impl Analyzer {
+    pub fn new() -> Analyzer {
+        Analyzer::default()
+    }
+
+    /// Walk the call graph from the exports and the start function to find
+    /// out which imported functions can actually be called. Off by default.
+    pub fn reachability(mut self, enabled: bool) -> Analyzer {
+        self.reachability = enabled;
+        self
+    }
+
+    /// Look for the ways a binary can behave differently when run twice
+    /// with the same input, such as reading clocks or computing NaNs. Off
+    /// by default.
+    pub fn determinism(mut self, enabled: bool) -> Analyzer {
+        self.determinism = enabled;
+        self
+    }
+
+    /// Validate binaries as CosmWasm contracts of the given interface
+    /// version, even if they do not look like contracts. Off by default,
+    /// contracts are then validated against the version they declare.
+    pub fn cosmwasm(mut self, interface_version: Option<u32>) -> Analyzer {
+        self.cosmwasm = interface_version;
+        self
+    }
+
+    /// Read the wasm module at `path` and analyze it.
+    pub fn analyze_file<P: AsRef<Path>>(&self, path: P) -> Result<Report, KontrolleurError> {
+        let bytes = fs::read(path)?;
+        self.analyze_bytes(&bytes)
+    }
+
+    /// Parse `bytes` as a wasm module or component and analyze it.
+    /// JavaScript with the binary embedded in it, as built by Emscripten's
+    /// `-sSINGLE_FILE`, is accepted as well.
+    pub fn analyze_bytes(&self, bytes: &[u8]) -> Result<Report, KontrolleurError> {
+        if !bytes.starts_with(b"\0asm") {
+            if let Some(binary) = emscripten::embedded_binary(bytes) {
+                let mut report = self.analyze_bytes(&binary)?;
+                report
+                    .assumptions
+                    .emscripten
+                    .get_or_insert_with(EmscriptenAssumptions::default)
+                    .single_file = true;
+                return Ok(report);
+            }
+        }
+        if parse::is_component(bytes) {
+            return self.analyze_component(bytes);
+        }
+        let module = parse::parse(bytes)?;
+        // The scan decodes every instruction, so it also finds malformed
+        // function bodies
+        let scan = proposals::scan(bytes);
+        if let Some(error) = scan.error {
+            return Err(error);
+        }
+        Ok(self.analyze(&module, scan.uses))
+    }
+
+    /// Analyze an already parsed parity-wasm module.
+    pub fn analyze_module(&self, module: &Module) -> Result<Report, KontrolleurError> {
+        let bytes = parity_wasm::serialize(module.clone()).map_err(|e| {
+            KontrolleurError::MalformedSection {
+                section: SectionId::Header,
+                offset: 0,
+                message: e.to_string(),
+            }
+        })?;
+        self.analyze_bytes(&bytes)
+    }
+
+    /// Find the shortest chains of calls from the exports and the start
+    /// function to every imported function matching `import`. The import is
+    /// written like an import rule of a [`Policy`], so `path_open` matches
+    /// the call of any WASI snapshot. For a component, the imports of each
+    /// of its core modules are explained.
+    pub fn explain(
+        &self,
+        bytes: &[u8],
+        import: &str,
+    ) -> Result<Vec<Explanation>, KontrolleurError> {
+        if parse::is_component(bytes) {
+            let parsed = component::parse(bytes)?;
+            let mut explanations = Vec::new();
+            for (index, nested) in parsed.modules.iter().enumerate() {
+                explanations.extend(explain::explain(&nested.module, import).into_iter().map(
+                    |explanation| Explanation {
+                        core_module: Some(index as u32),
+                        ..explanation
+                    },
+                ));
+            }
+            return Ok(explanations);
+        }
+        let module = parse::parse(bytes)?;
+        Ok(explain::explain(&module, import))
+    }
+
+    /// Sort the functions of the interfaces a component imports like the
+    /// imports of a module, and analyze each core module in it on its own
+    fn analyze_component(&self, bytes: &[u8]) -> Result<Report, KontrolleurError> {
+        let parsed = component::parse(bytes)?;
+        let mut component = parsed.assumptions;
+        let mut proposals: Vec<ProposalUse> = Vec::new();
+        // The component itself has no code, only its core modules do
+        let mut determinism = self.determinism.then(Vec::new);
+        for (index, nested) in parsed.modules.into_iter().enumerate() {
+            let scan = proposals::scan(nested.bytes);
+            if let Some(error) = scan.error {
+                return Err(parse::nested(error, nested.offset));
+            }
+            let mut report = self.analyze(&nested.module, scan.uses);
+            for proposal in &mut report.proposals {
+                proposal.offset += nested.offset;
+                // The modules come in the order of the binary, so the first
+                // module using a proposal has its first use
+                if proposals.iter().all(|p| p.proposal != proposal.proposal) {
+                    proposals.push(proposal.clone());
+                }
+            }
+            if let (Some(determinism), Some(found)) = (&mut determinism, &report.determinism) {
+                determinism.extend(found.iter().map(|finding| Nondeterminism {
+                    core_module: Some(index as u32),
+                    ..finding.clone()
+                }));
+            }
+            component.core_modules.push(CoreModule {
+                index: index as u32,
+                name: nested.name,
+                offset: nested.offset,
+                adapter: nested.adapter,
+                report,
+                links: nested.links,
+            });
+        }
+        let mut assumptions = Assumptions::new();
+        for import in component.imported_functions() {
+            match WasiSnapshot::from_module_name(&import.module) {
+                Some(snapshot) => assumptions.add_wasi(snapshot, import),
+                None => assumptions.add_unknown(import),
+            }
+        }
+        assumptions.component = Some(component);
+        Ok(Report {
+            assumptions,
+            proposals,
+            determinism,
+        })
+    }
+
+    fn analyze(&self, module: &ParsedModule, mut proposals: Vec<ProposalUse>) -> Report {
+        let mut assumptions = Assumptions::new();
+        assumptions.emscripten = EmscriptenAssumptions::detect(module);
+        assumptions.wasm_bindgen = WasmBindgenAssumptions::detect(module);
+        assumptions.go = GoAssumptions::detect(module);
+        assumptions.assemblyscript = AssemblyScriptAssumptions::detect(module);
+        assumptions.proxy_wasm = ProxyWasmAssumptions::detect(module);
+        assumptions.fastly = FastlyAssumptions::detect(module);
+        assumptions.extism = ExtismAssumptions::detect(module);
+        assumptions.near = NearAssumptions::detect(module);
+        assumptions.ic0 = Ic0Assumptions::detect(module);
+        assumptions.seal = SealAssumptions::detect(module);
+        assumptions.stylus = StylusAssumptions::detect(module);
+        assumptions.cosmwasm = CosmWasmAssumptions::detect(module, self.cosmwasm);
+        let reachability = if self.reachability {
+            CallGraph::new(module).import_reachability()
+        } else {
+            Vec::new()
+        };
+        let mut functions = 0;
+        for entry in &module.imports {
+            let mut import = Import::new(module, entry);
+            if import.kind == ImportKind::Function {
+                import.reachability = reachability.get(functions).copied();
+                functions += 1;
+            }
+            match (WasiSnapshot::from_module_name(&import.module), entry.ty) {
+                (Some(snapshot), _) => assumptions.add_wasi(snapshot, import),
+                (None, TypeRef::Memory(ty)) => {
+                    assumptions.add_memory(MemoryImport::new(import, &ty))
+                }
+                (None, TypeRef::Table(ty)) => assumptions.add_table(TableImport::new(import, &ty)),
+                (None, _) => assumptions.add_unknown(import),
+            }
+        }
+
+        let names = FunctionNames::new(module);
+        for proposal in &mut proposals {
+            proposal.function_name = proposal
+                .function
+                .and_then(|f| names.find(f))
+                .map(String::from);
+        }
+
+        Report {
+            assumptions,
+            proposals,
+            determinism: self.determinism.then(|| determinism::audit(module)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Assemble a module written in the text format
+    pub(crate) fn wasm(wat: &str) -> Vec<u8> {
+        wat::parse_str(wat).unwrap()
+    }
+
+    pub(crate) fn analyze(wat: &str) -> Report {
+        analyze_with(Analyzer::new(), wat)
+    }
+
+    pub(crate) fn analyze_with(analyzer: Analyzer, wat: &str) -> Report {
+        analyzer.analyze_bytes(&wasm(wat)).unwrap()
+    }
+
+    /// A function import of unknown type
+    pub(crate) fn import(module: &str, field: &str) -> Import {
+        Import {
+            module: module.to_owned(),
+            field: field.to_owned(),
+            kind: ImportKind::Function,
+            signature: None,
+            reachability: None,
+        }
+    }
+
+    pub(crate) fn fields(imports: &[Import]) -> Vec<&str> {
+        imports.iter().map(|i| i.field.as_str()).collect()
+    }
+
+    #[test]
+    fn sorts_imports_by_environment() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "host" "log" (func (param i32)))
+                (import "host" "memory" (memory 1)))"#,
+        );
+        let assumptions = &report.assumptions;
+        assert_eq!(assumptions.count(), 3);
+        assert_eq!(assumptions.wasi.snapshots, vec![WasiSnapshot::Preview1]);
+        assert_eq!(fields(&assumptions.wasi.file_system), vec!["fd_write"]);
+        assert_eq!(
+            assumptions.wasi.file_system[0].signature,
+            Some(Signature {
+                params: vec![ValueType::I32; 4],
+                results: vec![ValueType::I32],
+            })
+        );
+        assert_eq!(fields(&assumptions.unknown), vec!["log"]);
+        assert_eq!(assumptions.memories[0].import.field, "memory");
+        assert_eq!(assumptions.memories[0].import.kind, ImportKind::Memory);
+    }
+
+    #[test]
+    fn describes_imported_memories_and_tables() {
+        let report = analyze(
+            r#"(module
+                (import "env" "memory" (memory 2 16 shared))
+                (import "env" "table" (table 4 externref)))"#,
+        );
+        let memory = &report.assumptions.memories[0];
+        assert_eq!(
+            memory.limits,
+            Limits {
+                initial: 2,
+                maximum: Some(16)
+            }
+        );
+        assert!(memory.shared);
+        assert!(!memory.memory64);
+        let table = &report.assumptions.tables[0];
+        assert_eq!(table.element_type, ValueType::ExternRef);
+        assert_eq!(table.limits.maximum, None);
+        let categories: Vec<_> = report
+            .assumptions
+            .categorized()
+            .into_iter()
+            .map(|(category, _)| category)
+            .collect();
+        assert_eq!(categories, vec!["memory", "table"]);
+    }
+
+    #[test]
+    fn reachability_is_opt_in() {
+        let wat = r#"(module
+            (import "host" "used" (func $used))
+            (import "host" "unused" (func $unused))
+            (func (export "run") call $used)
+            (func call $unused))"#;
+        let report = analyze(wat);
+        assert!(report
+            .assumptions
+            .unknown
+            .iter()
+            .all(|i| i.reachability.is_none()));
+
+        let report = analyze_with(Analyzer::new().reachability(true), wat);
+        let reachability: Vec<_> = report
+            .assumptions
+            .unknown
+            .iter()
+            .map(|i| i.reachability)
+            .collect();
+        assert_eq!(
+            reachability,
+            vec![
+                Some(Reachability::Reachable),
+                Some(Reachability::Unreachable)
+            ]
+        );
+    }
+
+    #[test]
+    fn analyzes_parity_modules() {
+        let bytes = wasm(r#"(module (import "host" "log" (func)))"#);
+        let module = parity_wasm::deserialize_buffer::<Module>(&bytes).unwrap();
+        let report = Analyzer::new().analyze_module(&module).unwrap();
+        assert_eq!(fields(&report.assumptions.unknown), vec!["log"]);
+    }
+
+    #[test]
+    fn rejects_binaries_that_are_not_wasm() {
+        assert!(matches!(
+            Analyzer::new().analyze_bytes(b"not wasm"),
+            Err(KontrolleurError::BadMagic)
+        ));
+    }
+}
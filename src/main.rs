@@ -1,5 +1,9 @@
-use parity_wasm::{deserialize_buffer, elements::Module};
-use std::fs::read;
+use kontrolleur::{Analyzer, Diff, KontrolleurError, Policy, Report};
+use std::fs;
+use std::io::stdout;
+use std::process::exit;
+use std::str::FromStr;
+use structopt::clap::{Error, ErrorKind};
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -8,210 +12,255 @@ use structopt::StructOpt;
     about = "Inspecting what assumptions a wasm binary has about its environment"
 )]
 struct Options {
+    #[structopt(subcommand)]
+    command: Option<Command>,
     /// Input file
     #[structopt()]
-    file: String,
+    file: Option<String>,
     /// Verbose output
     #[structopt(long = "verbose")]
     verbose: bool,
+    /// Output format: text or json
+    #[structopt(long = "format", default_value = "text")]
+    format: Format,
+    /// Walk the calls from the exports and the start function to find
+    /// imports no code path calls
+    #[structopt(long = "reachability")]
+    reachability: bool,
+    /// Report the ways the binary can behave differently when run twice
+    /// with the same input
+    #[structopt(long = "determinism")]
+    determinism: bool,
+    /// Validate the binary as a CosmWasm contract of the given interface
+    /// version
+    #[structopt(long = "cosmwasm")]
+    cosmwasm: Option<u32>,
+    /// Check the binary against the allow and deny rules in a policy file
+    #[structopt(long = "policy")]
+    policy: Option<String>,
+    /// Exit with an error when the binary breaks the rules of a toolchain
+    /// profile
+    #[structopt(long = "strict")]
+    strict: bool,
 }
 
-fn main() {
-    let options = Options::from_args();
-    let contents = read(options.file).expect("Failed to read file");
-    let module = deserialize_buffer::<Module>(&contents).unwrap();
-    let import_section = module.import_section();
-    let mut assumptions = Assumptions::new();
-    let entries = import_section.map(|s| s.entries());
-    if let Some(entries) = entries {
-        for import in entries {
-            match import.module() {
-                "wasi_unstable" => assumptions.add_wasi(import.field()),
-                _ => assumptions.add_unknown(import.field()),
-            }
-        }
-    }
-
-    report(assumptions, options.verbose);
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Compare the assumptions of two versions of a binary
+    #[structopt(name = "diff")]
+    Diff {
+        /// The old version of the binary
+        old: String,
+        /// The new version of the binary
+        new: String,
+        /// Output format: text or json
+        #[structopt(long = "format", default_value = "text")]
+        format: Format,
+        /// Exit with an error when the new version uses categories the old
+        /// one does not
+        #[structopt(long = "fail-on-new-categories")]
+        fail_on_new_categories: bool,
+    },
+    /// Show the shortest chains of calls from the exports and the start
+    /// function to an import
+    #[structopt(name = "explain")]
+    Explain {
+        /// Input file
+        file: String,
+        /// The import to explain, as `field` or `module::field`
+        import: String,
+    },
 }
 
-struct WasiAssumptions<'a> {
-    file_system: Vec<&'a str>,
-    environment: Vec<&'a str>,
-    process: Vec<&'a str>,
-    network: Vec<&'a str>,
-    unknown: Vec<&'a str>,
+#[derive(Debug)]
+enum Format {
+    Text,
+    Json,
 }
 
-impl<'a> WasiAssumptions<'a> {
-    fn new() -> WasiAssumptions<'a> {
-        WasiAssumptions {
-            file_system: Vec::new(),
-            environment: Vec::new(),
-            process: Vec::new(),
-            network: Vec::new(),
-            unknown: Vec::new(),
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown format '{}', expected text or json", s)),
         }
     }
-    fn add(&mut self, name: &'a str) {
-        match name {
-            "args_get" | "args_sizes_get" | "clock_res_get" | "clock_time_get" | "random_get"
-            | "environ_get" | "environ_sizes_get" => self.environment.push(name),
-            "fd_advise"
-            | "fd_close"
-            | "fd_datasync"
-            | "fd_fdstat_get"
-            | "fd_fdstat_set_flags"
-            | "fd_fdstat_set_rights"
-            | "fd_filestat_get"
-            | "fd_filestat_set_size"
-            | "fd_filestat_set_times"
-            | "fd_pread"
-            | "fd_prestat_get"
-            | "fd_prestat_dir_name"
-            | "fd_pwrite"
-            | "fd_read"
-            | "fd_readdir"
-            | "fd_renumber"
-            | "fd_seek"
-            | "fd_sync"
-            | "fd_tell"
-            | "fd_write"
-            | "path_create_directory"
-            | "path_filestat_get"
-            | "path_filestat_set_times"
-            | "path_link"
-            | "path_open"
-            | "path_readlink"
-            | "path_remove_directory"
-            | "path_rename"
-            | "path_symlink"
-            | "path_unlink_file"
-            | "poll_oneoff" => self.file_system.push(name),
-            "proc_exit" | "proc_raise" | "sched_yield" => self.process.push(name),
-            "sock_recv" | "sock_send" | "sock_shutdown" => self.network.push(name),
-            _ => self.unknown.push(name),
-        }
+}
+
+/// Exit codes for the different ways analysis can fail, so batch jobs can
+/// tell them apart
+const EXIT_IO: i32 = 2;
+const EXIT_NOT_WASM: i32 = 3;
+const EXIT_MALFORMED: i32 = 4;
+const EXIT_UNSUPPORTED: i32 = 5;
+const EXIT_POLICY_VIOLATED: i32 = 6;
+const EXIT_NEW_CATEGORIES: i32 = 7;
+const EXIT_SIGNATURE_MISMATCH: i32 = 8;
+const EXIT_PROFILE_VIOLATED: i32 = 9;
+
+fn main() {
+    let options = Options::from_args();
+    match &options.command {
+        Some(Command::Diff {
+            old,
+            new,
+            format,
+            fail_on_new_categories,
+        }) => return diff(old, new, format, *fail_on_new_categories),
+        Some(Command::Explain { file, import }) => return explain(file, import),
+        None => {}
     }
 
-    fn count(&self) -> usize {
-        self.file_system.len()
-            + self.process.len()
-            + self.environment.len()
-            + self.network.len()
-            + self.unknown.len()
+    let file = match &options.file {
+        Some(file) => file,
+        None => Error::with_description(
+            "The following required arguments were not provided:\n    <file>",
+            ErrorKind::MissingRequiredArgument,
+        )
+        .exit(),
+    };
+    let analyzer = Analyzer::new()
+        .reachability(options.reachability)
+        .determinism(options.determinism)
+        .cosmwasm(options.cosmwasm);
+    let report = analyze(&analyzer, file);
+
+    if let Some(policy) = &options.policy {
+        check(policy, &report);
+        return;
     }
-}
 
-struct Assumptions<'a> {
-    wasi: WasiAssumptions<'a>,
-    unknown: Vec<&'a str>,
+    match options.format {
+        Format::Text => report
+            .write_text(stdout().lock(), options.verbose)
+            .expect("Failed to write report"),
+        Format::Json => json(&report),
+    }
+    if report.assumptions.has_signature_mismatches() {
+        exit(EXIT_SIGNATURE_MISMATCH);
+    }
+    if options.strict && report.assumptions.has_profile_violations() {
+        exit(EXIT_PROFILE_VIOLATED);
+    }
 }
 
-impl<'a> Assumptions<'a> {
-    fn new() -> Assumptions<'a> {
-        Assumptions {
-            wasi: WasiAssumptions::new(),
-            unknown: Vec::new(),
-        }
+fn analyze(analyzer: &Analyzer, file: &str) -> Report {
+    match analyzer.analyze_file(file) {
+        Ok(report) => report,
+        Err(e) => fail(file, &e),
     }
+}
 
-    fn add_wasi(&mut self, name: &'a str) {
-        self.wasi.add(name)
+fn diff(old: &str, new: &str, format: &Format, fail_on_new_categories: bool) {
+    let diff = Diff::new(
+        &analyze(&Analyzer::new(), old).assumptions,
+        &analyze(&Analyzer::new(), new).assumptions,
+    );
+    match format {
+        Format::Text => diff
+            .write_text(stdout().lock())
+            .expect("Failed to write diff"),
+        Format::Json => diff_json(&diff),
     }
-
-    fn add_unknown(&mut self, name: &'a str) {
-        self.unknown.push(name)
+    if fail_on_new_categories && !diff.added_categories.is_empty() {
+        exit(EXIT_NEW_CATEGORIES);
     }
+}
 
-    fn count(&self) -> usize {
-        self.unknown.len() + self.wasi.count()
+fn explain(file: &str, import: &str) {
+    let explanations = match fs::read(file) {
+        Ok(bytes) => Analyzer::new().explain(&bytes, import),
+        Err(e) => Err(e.into()),
+    };
+    let explanations = explanations.unwrap_or_else(|e| fail(file, &e));
+    if explanations.is_empty() {
+        eprintln!("error: {}: the binary does not import {}", file, import);
+        exit(1);
     }
+    for explanation in &explanations {
+        explanation
+            .write_text(stdout().lock())
+            .expect("Failed to write explanation");
+    }
+}
+
+fn fail(file: &str, error: &KontrolleurError) -> ! {
+    eprintln!("error: {}: {}", file, error);
+    let code = match error {
+        KontrolleurError::Io(_) => EXIT_IO,
+        KontrolleurError::BadMagic | KontrolleurError::BadVersion { .. } => EXIT_NOT_WASM,
+        KontrolleurError::MalformedSection { .. } => EXIT_MALFORMED,
+        KontrolleurError::UnsupportedProposal { .. } => EXIT_UNSUPPORTED,
+    };
+    exit(code)
 }
-fn report<'a>(assumptions: Assumptions<'a>, verbose: bool) {
-    let total_count = assumptions.count();
+
+fn check(path: &str, report: &Report) {
+    let violations = load_policy(path).check(&report.assumptions);
+    if violations.is_empty() {
+        println!("The binary complies with the policy.");
+        return;
+    }
     println!(
-        "There {} {} total external API call{}.",
-        correct_to_be_form(total_count),
-        total_count,
-        optional_s(total_count)
+        "The binary violates the policy {} time{}:",
+        violations.len(),
+        if violations.len() == 1 { "" } else { "s" }
     );
+    for violation in &violations {
+        println!("\t{}", violation);
+    }
+    exit(EXIT_POLICY_VIOLATED);
+}
 
-    let wasi = assumptions.wasi;
-    let wasi_count = wasi.count();
-    if wasi_count > 0 {
-        println!("This binary is expecting a WASI compliant runtime.");
-        println!(
-            "\tThe binary uses {} WASI call{}",
-            wasi_count,
-            optional_s(wasi_count)
-        );
-        println!("\tThe following system resource types are used:");
-        let mut types = Vec::new();
-        if wasi.file_system.len() > 0 {
-            types.push("file system");
-        }
-        if wasi.environment.len() > 0 {
-            types.push("environment");
-        }
-        if wasi.process.len() > 0 {
-            types.push("process");
+#[cfg(feature = "serde")]
+fn load_policy(path: &str) -> Policy {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("error: {}: failed to read the policy: {}", path, e);
+            exit(EXIT_IO);
         }
-        println!("\t\t{}", types.join(", "));
-        if verbose {
-            if wasi.file_system.len() > 0 {
-                println!("\tFile system calls:");
-                for call in wasi.file_system {
-                    println!("\t\t{}", call);
-                }
-            }
-            if wasi.environment.len() > 0 {
-                println!("\tEnivronent system calls:");
-                for call in wasi.environment {
-                    println!("\t\t{}", call);
-                }
-            }
-            if wasi.process.len() > 0 {
-                println!("\tProcess system calls:");
-                for call in wasi.process {
-                    println!("\t\t{}", call);
-                }
-            }
-        }
-        let unknown = wasi.unknown;
-        let unknown_count = unknown.len();
-        if unknown_count > 0 {
-            println!(
-                "There {} {} unknown wasi sys call{}:",
-                correct_to_be_form(unknown_count),
-                unknown_count,
-                optional_s(unknown_count)
-            );
-            for call in unknown {
-                println!("\t{}", call);
-            }
+    };
+    match Policy::from_toml(&contents) {
+        Ok(policy) => policy,
+        Err(e) => {
+            eprintln!("error: {}: invalid policy: {}", path, e);
+            exit(EXIT_IO);
         }
     }
+}
 
-    if assumptions.unknown.len() > 0 {
-        println!("Unknown imports:");
-        for unknown in assumptions.unknown {
-            println!("\t{}", unknown);
-        }
-    }
+#[cfg(not(feature = "serde"))]
+fn load_policy(_: &str) -> Policy {
+    eprintln!("kontrolleur was built without the serde feature, policies are unavailable");
+    exit(1);
 }
 
-fn correct_to_be_form(count: usize) -> &'static str {
-    if count == 1 {
-        "is"
-    } else {
-        "are"
-    }
+#[cfg(feature = "serde")]
+fn json(report: &Report) {
+    println!("{}", kontrolleur::json::to_string(report));
 }
-fn optional_s(count: usize) -> &'static str {
-    if count == 1 {
-        ""
-    } else {
-        "s"
-    }
+
+#[cfg(feature = "serde")]
+fn diff_json(diff: &Diff) {
+    println!("{}", kontrolleur::json::diff_to_string(diff));
+}
+
+#[cfg(not(feature = "serde"))]
+fn json(_: &Report) {
+    no_json()
+}
+
+#[cfg(not(feature = "serde"))]
+fn diff_json(_: &Diff) {
+    no_json()
+}
+
+#[cfg(not(feature = "serde"))]
+fn no_json() {
+    eprintln!("kontrolleur was built without the serde feature, JSON output is unavailable");
+    exit(1);
 }
@@ -0,0 +1,216 @@
+use crate::Signature;
+use wasmparser::{
+    CompositeInnerType, Dylink0Subsection, ElementItems, ElementKind, Export, ExternalKind,
+    FunctionBody, Import, KnownCustom, MemoryType, Name, Operator, Parser, Payload, TableType,
+    TypeRef,
+};
+
+/// The parts of a wasm module kontrolleur looks at. Unlike parity-wasm,
+/// wasmparser reads binaries using any post-MVP proposal.
+pub(crate) struct ParsedModule<'a> {
+    /// The module's types, `None` for types that are not function types
+    pub(crate) types: Vec<Option<Signature>>,
+    pub(crate) imports: Vec<Import<'a>>,
+    /// The type index of every function, imported functions first
+    pub(crate) functions: Vec<u32>,
+    pub(crate) imported_functions: u32,
+    /// The tables and memories of the module, imported ones first
+    pub(crate) tables: Vec<TableType>,
+    pub(crate) memories: Vec<MemoryType>,
+    pub(crate) exports: Vec<Export<'a>>,
+    pub(crate) start: Option<u32>,
+    /// The functions placed in tables by element segments
+    pub(crate) table_members: Vec<u32>,
+    /// The slots active element segments with a constant offset fill, as
+    /// the table, the slot and the function placed in it
+    pub(crate) table_slots: Vec<(u32, u64, u32)>,
+    /// The bodies of the functions the module defines
+    pub(crate) bodies: Vec<FunctionBody<'a>>,
+    /// The function names of the name section
+    pub(crate) names: Vec<(u32, &'a str)>,
+    /// The names of all custom sections
+    pub(crate) custom_sections: Vec<&'a str>,
+    /// The shared libraries a dynamically linked module needs, from its
+    /// `dylink.0` section
+    pub(crate) needed_libraries: Vec<&'a str>,
+}
+
+impl<'a> ParsedModule<'a> {
+    pub(crate) fn parse(bytes: &'a [u8]) -> wasmparser::Result<ParsedModule<'a>> {
+        let mut module = ParsedModule {
+            types: Vec::new(),
+            imports: Vec::new(),
+            functions: Vec::new(),
+            imported_functions: 0,
+            tables: Vec::new(),
+            memories: Vec::new(),
+            exports: Vec::new(),
+            start: None,
+            table_members: Vec::new(),
+            table_slots: Vec::new(),
+            bodies: Vec::new(),
+            names: Vec::new(),
+            custom_sections: Vec::new(),
+            needed_libraries: Vec::new(),
+        };
+        for payload in Parser::new(0).parse_all(bytes) {
+            match payload? {
+                Payload::TypeSection(reader) => {
+                    for group in reader {
+                        for ty in group?.into_types() {
+                            module.types.push(match &ty.composite_type.inner {
+                                CompositeInnerType::Func(f) => Some(f.into()),
+                                _ => None,
+                            });
+                        }
+                    }
+                }
+                Payload::ImportSection(reader) => {
+                    for import in reader.into_imports() {
+                        let import = import?;
+                        match import.ty {
+                            TypeRef::Func(index) | TypeRef::FuncExact(index) => {
+                                module.functions.push(index);
+                                module.imported_functions += 1;
+                            }
+                            TypeRef::Table(ty) => module.tables.push(ty),
+                            TypeRef::Memory(ty) => module.memories.push(ty),
+                            _ => {}
+                        }
+                        module.imports.push(import);
+                    }
+                }
+                Payload::FunctionSection(reader) => {
+                    for index in reader {
+                        module.functions.push(index?);
+                    }
+                }
+                Payload::TableSection(reader) => {
+                    for table in reader {
+                        module.tables.push(table?.ty);
+                    }
+                }
+                Payload::MemorySection(reader) => {
+                    for memory in reader {
+                        module.memories.push(memory?);
+                    }
+                }
+                Payload::ExportSection(reader) => {
+                    for export in reader {
+                        module.exports.push(export?);
+                    }
+                }
+                Payload::StartSection { func, .. } => module.start = Some(func),
+                Payload::ElementSection(reader) => {
+                    for element in reader {
+                        let element = element?;
+                        let slots = match element.kind {
+                            ElementKind::Active {
+                                table_index,
+                                offset_expr,
+                            } => match offset_expr.get_operators_reader().read()? {
+                                Operator::I32Const { value } => {
+                                    Some((table_index.unwrap_or(0), value as u32 as u64))
+                                }
+                                Operator::I64Const { value } => {
+                                    Some((table_index.unwrap_or(0), value as u64))
+                                }
+                                _ => None,
+                            },
+                            _ => None,
+                        };
+                        let mut members = Vec::new();
+                        match element.items {
+                            ElementItems::Functions(functions) => {
+                                for function in functions {
+                                    members.push(Some(function?));
+                                }
+                            }
+                            ElementItems::Expressions(_, expressions) => {
+                                for expression in expressions {
+                                    let mut operators = expression?.get_operators_reader();
+                                    let mut member = None;
+                                    while !operators.eof() {
+                                        if let Operator::RefFunc { function_index } =
+                                            operators.read()?
+                                        {
+                                            member = Some(function_index);
+                                        }
+                                    }
+                                    members.push(member);
+                                }
+                            }
+                        }
+                        for (slot, member) in members.into_iter().enumerate() {
+                            if let Some(function) = member {
+                                module.table_members.push(function);
+                                if let Some((table, offset)) = slots {
+                                    module.table_slots.push((
+                                        table,
+                                        offset + slot as u64,
+                                        function,
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                }
+                Payload::CodeSectionEntry(body) => module.bodies.push(body),
+                Payload::CustomSection(reader) => {
+                    module.custom_sections.push(reader.name());
+                    // A broken custom section only costs us what is in it
+                    match reader.as_known() {
+                        KnownCustom::Name(reader) => {
+                            for name in reader.into_iter().flatten() {
+                                if let Name::Function(names) = name {
+                                    module.names.extend(
+                                        names.into_iter().flatten().map(|n| (n.index, n.name)),
+                                    );
+                                }
+                            }
+                        }
+                        KnownCustom::Dylink0(reader) => {
+                            for subsection in reader.into_iter().flatten() {
+                                if let Dylink0Subsection::Needed(needed) = subsection {
+                                    module.needed_libraries.extend(needed);
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(module)
+    }
+
+    /// The type of the function at `index`, imported functions first
+    pub(crate) fn function_signature(&self, index: u32) -> Option<&Signature> {
+        let ty = *self.functions.get(index as usize)?;
+        self.types.get(ty as usize)?.as_ref()
+    }
+
+    /// The import an index of the given kind refers to, if the item is
+    /// imported rather than defined in the module
+    pub(crate) fn imported_item(&self, kind: ExternalKind, index: u32) -> Option<&Import<'a>> {
+        self.imports
+            .iter()
+            .filter(|import| match import.ty {
+                TypeRef::Func(_) | TypeRef::FuncExact(_) => kind == ExternalKind::Func,
+                TypeRef::Table(_) => kind == ExternalKind::Table,
+                TypeRef::Memory(_) => kind == ExternalKind::Memory,
+                TypeRef::Global(_) => kind == ExternalKind::Global,
+                TypeRef::Tag(_) => kind == ExternalKind::Tag,
+            })
+            .nth(index as usize)
+    }
+
+    /// The type of the exported function named `name`
+    pub(crate) fn export_signature(&self, name: &str) -> Option<&Signature> {
+        self.exports
+            .iter()
+            .find(|e| e.name == name && e.kind == ExternalKind::Func)
+            .and_then(|e| self.function_signature(e.index))
+    }
+}
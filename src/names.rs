@@ -0,0 +1,70 @@
+use crate::module::ParsedModule;
+use wasmparser::TypeRef;
+
+/// Readable names for the functions of a module, taken from the name
+/// section with Rust and C++ symbols demangled. Imported functions are
+/// named `module::field`.
+pub(crate) struct FunctionNames {
+    names: Vec<Option<String>>,
+}
+
+impl FunctionNames {
+    pub(crate) fn new(module: &ParsedModule) -> FunctionNames {
+        let mut names: Vec<_> = module
+            .imports
+            .iter()
+            .filter(|i| matches!(i.ty, TypeRef::Func(_) | TypeRef::FuncExact(_)))
+            .map(|i| Some(format!("{}::{}", i.module, i.name)))
+            .collect();
+        names.resize(module.functions.len(), None);
+        for &(index, name) in &module.names {
+            if let Some(slot @ None) = names.get_mut(index as usize) {
+                *slot = Some(demangle(name));
+            }
+        }
+        FunctionNames { names }
+    }
+
+    /// The name of the function, made up from its index if the binary does
+    /// not name it
+    pub(crate) fn get(&self, index: u32) -> String {
+        match self.find(index) {
+            Some(name) => name.to_owned(),
+            None => format!("function {}", index),
+        }
+    }
+
+    pub(crate) fn find(&self, index: u32) -> Option<&str> {
+        self.names.get(index as usize)?.as_deref()
+    }
+}
+
+/// Demangle a Rust or C++ symbol, leaving other names as they are
+pub(crate) fn demangle(name: &str) -> String {
+    if let Ok(demangled) = rustc_demangle::try_demangle(name) {
+        // The alternate form leaves out the hash at the end
+        return format!("{:#}", demangled);
+    }
+    if name.starts_with("_Z") {
+        if let Ok(demangled) = cpp_demangle::Symbol::new(name).map(|s| s.to_string()) {
+            return demangled;
+        }
+    }
+    name.to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demangles_rust_and_cpp_symbols() {
+        assert_eq!(
+            demangle("_ZN4core3fmt5write17h0123456789abcdefE"),
+            "core::fmt::write"
+        );
+        assert_eq!(demangle("_ZN3foo3barEv"), "foo::bar()");
+        assert_eq!(demangle("main"), "main");
+        assert_eq!(demangle("_Znot a symbol"), "_Znot a symbol");
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{
+    module::ParsedModule,
+    profile::{self, Classifier, EntryPoint},
+    Import, Profile,
+};
+
+/// The NEAR host functions a contract calls, grouped by what they give
+/// access to
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NearAssumptions {
+    pub storage: Vec<Import>,
+    /// Cross-contract calls, their callbacks and the actions of batched
+    /// transactions
+    pub promises: Vec<Import>,
+    /// The accounts, balances, gas and block the contract runs with
+    pub account: Vec<Import>,
+    pub crypto: Vec<Import>,
+    pub logging: Vec<Import>,
+    /// The registers host functions hand data back in, the method's input
+    /// and its return value
+    pub io: Vec<Import>,
+    pub process: Vec<Import>,
+    /// The categories of the calls each exported method makes
+    pub methods: Vec<EntryPoint>,
+}
+
+/// The order the categories of a method are listed in
+const CATEGORIES: &[&str] = &[
+    "storage", "promises", "account", "crypto", "logging", "io", "process",
+];
+
+impl NearAssumptions {
+    /// Recognize a NEAR contract by the host functions only NEAR provides
+    pub(crate) fn detect(module: &ParsedModule) -> Option<NearAssumptions> {
+        let near = module.imports.iter().any(|i| {
+            i.module == "env"
+                && matches!(
+                    i.name,
+                    "read_register"
+                        | "register_len"
+                        | "predecessor_account_id"
+                        | "current_account_id"
+                        | "attached_deposit"
+                        | "storage_write"
+                        | "promise_create"
+                        | "promise_batch_create"
+                        | "value_return"
+                )
+        });
+        if !near {
+            return None;
+        }
+        // Only imports of env can be NEAR calls
+        let methods = profile::entry_points(module, CATEGORIES, |module, name| {
+            category(name).filter(|_| module == "env")
+        });
+        Some(NearAssumptions {
+            methods,
+            ..NearAssumptions::default()
+        })
+    }
+}
+
+impl Classifier for NearAssumptions {
+    fn classify(&mut self, import: Import) -> Result<(), Import> {
+        if import.module != "env" {
+            return Err(import);
+        }
+        let calls = match category(&import.field) {
+            Some("storage") => &mut self.storage,
+            Some("promises") => &mut self.promises,
+            Some("account") => &mut self.account,
+            Some("crypto") => &mut self.crypto,
+            Some("logging") => &mut self.logging,
+            Some("io") => &mut self.io,
+            Some("process") => &mut self.process,
+            _ => return Err(import),
+        };
+        calls.push(import);
+        Ok(())
+    }
+}
+
+impl Profile for NearAssumptions {
+    fn id(&self) -> &'static str {
+        "near"
+    }
+
+    fn name(&self) -> &'static str {
+        "NEAR"
+    }
+
+    fn categories(&self) -> Vec<(&'static str, &[Import])> {
+        vec![
+            ("storage", &self.storage),
+            ("promises", &self.promises),
+            ("account", &self.account),
+            ("crypto", &self.crypto),
+            ("logging", &self.logging),
+            ("io", &self.io),
+            ("process", &self.process),
+        ]
+    }
+
+    fn entry_points(&self) -> &[EntryPoint] {
+        &self.methods
+    }
+}
+
+/// The category of a NEAR host function
+fn category(name: &str) -> Option<&'static str> {
+    let category = match name {
+        name if name.starts_with("storage_") && name != "storage_usage" => "storage",
+        name if name.starts_with("promise_") => "promises",
+        "current_account_id"
+        | "signer_account_id"
+        | "signer_account_pk"
+        | "predecessor_account_id"
+        | "account_balance"
+        | "account_locked_balance"
+        | "attached_deposit"
+        | "prepaid_gas"
+        | "used_gas"
+        | "storage_usage"
+        | "block_index"
+        | "block_timestamp"
+        | "epoch_height"
+        | "validator_stake"
+        | "validator_total_stake" => "account",
+        "random_seed" | "sha256" | "keccak256" | "keccak512" | "ripemd160" | "ecrecover"
+        | "ed25519_verify" => "crypto",
+        name if name.starts_with("alt_bn128_") || name.starts_with("bls12381_") => "crypto",
+        "log_utf8" | "log_utf16" => "logging",
+        "read_register" | "register_len" | "write_register" | "input" | "value_return" => "io",
+        "panic" | "panic_utf8" | "abort" => "process",
+        _ => return None,
+    };
+    Some(category)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, fields};
+
+    const CONTRACT: &str = r#"(module
+        (import "env" "storage_write" (func $storage_write (param i64 i64 i64 i64 i64) (result i64)))
+        (import "env" "promise_create" (func $promise_create (param i64 i64 i64 i64 i64 i64 i64 i64) (result i64)))
+        (import "env" "predecessor_account_id" (func $predecessor (param i64)))
+        (import "env" "sha256" (func (param i64 i64 i64)))
+        (import "env" "log_utf8" (func $log (param i64 i64)))
+        (import "env" "input" (func $input (param i64)))
+        (import "env" "panic_utf8" (func (param i64 i64)))
+        (import "env" "memcpy" (func (param i32 i32 i32) (result i32)))
+        (func $save
+            i64.const 0 i64.const 0 i64.const 0 i64.const 0 i64.const 0
+            call $storage_write drop)
+        (func (export "set_greeting")
+            i64.const 0 call $input
+            i64.const 0 call $predecessor
+            call $save
+            i64.const 0 i64.const 0 call $log)
+        (func (export "get_greeting")
+            i64.const 0 call $input))"#;
+
+    #[test]
+    fn sorts_host_functions() {
+        let report = analyze(CONTRACT);
+        let near = report.assumptions.near.as_ref().unwrap();
+        assert_eq!(fields(&near.storage), vec!["storage_write"]);
+        assert_eq!(fields(&near.promises), vec!["promise_create"]);
+        assert_eq!(fields(&near.account), vec!["predecessor_account_id"]);
+        assert_eq!(fields(&near.crypto), vec!["sha256"]);
+        assert_eq!(fields(&near.logging), vec!["log_utf8"]);
+        assert_eq!(fields(&near.io), vec!["input"]);
+        assert_eq!(fields(&near.process), vec!["panic_utf8"]);
+        assert_eq!(fields(&report.assumptions.unknown), vec!["memcpy"]);
+        assert!(near.errors().is_empty());
+    }
+
+    #[test]
+    fn lists_the_categories_each_method_uses() {
+        let report = analyze(CONTRACT);
+        let methods = report.assumptions.near.unwrap().methods;
+        assert_eq!(
+            methods,
+            vec![
+                EntryPoint {
+                    export: "set_greeting".to_owned(),
+                    categories: vec![
+                        "storage".to_owned(),
+                        "account".to_owned(),
+                        "logging".to_owned(),
+                        "io".to_owned()
+                    ],
+                    indirect_categories: Vec::new(),
+                },
+                EntryPoint {
+                    export: "get_greeting".to_owned(),
+                    categories: vec!["io".to_owned()],
+                    indirect_categories: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_wasi_modules_are_not_contracts() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "env" "abort" (func))
+                (import "env" "sha256" (func))
+                (func (export "_start")))"#,
+        );
+        assert!(report.assumptions.near.is_none());
+        assert_eq!(fields(&report.assumptions.unknown), vec!["abort", "sha256"]);
+    }
+}
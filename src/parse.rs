@@ -0,0 +1,210 @@
+use crate::{module::ParsedModule, KontrolleurError, Proposal, SectionId};
+use std::ops::Range;
+
+const MAGIC: &[u8] = b"\0asm";
+const VERSION: u32 = 1;
+/// The version and layer fields of a component, which take the place of the
+/// version of a module
+const COMPONENT_VERSION: &[u8] = &[0x0d, 0x00, 0x01, 0x00];
+
+/// Parse `bytes` as a wasm module, describing where and why it failed if it
+/// could not be parsed
+pub(crate) fn parse(bytes: &[u8]) -> Result<ParsedModule<'_>, KontrolleurError> {
+    check_header(bytes)?;
+    ParsedModule::parse(bytes).map_err(|e| malformed(bytes, e))
+}
+
+/// Locate a wasmparser error in the binary
+pub(crate) fn malformed(bytes: &[u8], error: wasmparser::BinaryReaderError) -> KontrolleurError {
+    let offset = error.offset();
+    let section = section_at(bytes, offset);
+    // wasmparser reports an instruction it does not know at the prefix byte of
+    // its opcode, which tells the proposal it belongs to
+    let proposal = match bytes.get(offset) {
+        Some(0xfb) => Some(Proposal::Gc),
+        Some(0xfd) => Some(Proposal::Simd),
+        Some(0xfe) => Some(Proposal::Threads),
+        _ => None,
+    };
+    match proposal {
+        Some(proposal) if section == SectionId::Code => KontrolleurError::UnsupportedProposal {
+            proposal,
+            section,
+            offset,
+        },
+        _ => KontrolleurError::MalformedSection {
+            section,
+            offset,
+            message: error.message().to_owned(),
+        },
+    }
+}
+
+/// Whether `bytes` start like a component rather than a core module
+pub(crate) fn is_component(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC) && bytes[MAGIC.len()..].starts_with(COMPONENT_VERSION)
+}
+
+/// Move an error found in a module embedded at `offset` in a component to
+/// the offset it is at in the component
+pub(crate) fn nested(error: KontrolleurError, offset: usize) -> KontrolleurError {
+    match error {
+        KontrolleurError::MalformedSection {
+            section,
+            offset: position,
+            message,
+        } => KontrolleurError::MalformedSection {
+            section,
+            offset: offset + position,
+            message,
+        },
+        KontrolleurError::UnsupportedProposal {
+            proposal,
+            section,
+            offset: position,
+        } => KontrolleurError::UnsupportedProposal {
+            proposal,
+            section,
+            offset: offset + position,
+        },
+        error => error,
+    }
+}
+
+fn check_header(bytes: &[u8]) -> Result<(), KontrolleurError> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(KontrolleurError::BadMagic);
+    }
+    if bytes.len() < 8 {
+        return Err(KontrolleurError::MalformedSection {
+            section: SectionId::Header,
+            offset: bytes.len(),
+            message: "unexpected end of file in the version".to_owned(),
+        });
+    }
+    let version = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    if version != VERSION {
+        return Err(KontrolleurError::BadVersion { version });
+    }
+    Ok(())
+}
+
+/// The sections of the binary with the range they occupy, including their
+/// header. Stops at the first section that does not fit in the binary.
+fn sections(bytes: &[u8]) -> Vec<(SectionId, Range<usize>)> {
+    let mut sections = Vec::new();
+    let mut offset = 8;
+    while offset < bytes.len() {
+        let id = bytes[offset];
+        let (size, length) = match read_var_u32(&bytes[offset + 1..]) {
+            Some(size) => size,
+            None => break,
+        };
+        let end = offset + 1 + length + size as usize;
+        if end > bytes.len() {
+            sections.push((SectionId::from_id(id), offset..bytes.len()));
+            break;
+        }
+        sections.push((SectionId::from_id(id), offset..end));
+        offset = end;
+    }
+    sections
+}
+
+/// The section the given byte offset falls into. The sections of components
+/// are numbered differently from the ones of modules and are not told
+/// apart.
+pub(crate) fn section_at(bytes: &[u8], offset: usize) -> SectionId {
+    if is_component(bytes) {
+        return if offset < 8 {
+            SectionId::Header
+        } else {
+            SectionId::Component
+        };
+    }
+    sections(bytes)
+        .into_iter()
+        .take_while(|(_, range)| range.start <= offset)
+        .last()
+        .map_or(SectionId::Header, |(id, _)| id)
+}
+
+/// Read an unsigned LEB128 encoded u32, returning it and the number of
+/// bytes it took up
+fn read_var_u32(bytes: &[u8]) -> Option<(u32, usize)> {
+    let mut result = 0u32;
+    for (i, byte) in bytes.iter().take(5).enumerate() {
+        result |= u32::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((result, i + 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_the_header() {
+        assert!(matches!(
+            parse(b"not wasm"),
+            Err(KontrolleurError::BadMagic)
+        ));
+        assert!(matches!(
+            parse(b"\0asm\x02\0\0\0"),
+            Err(KontrolleurError::BadVersion { version: 2 })
+        ));
+        assert!(matches!(
+            parse(b"\0asm\x01"),
+            Err(KontrolleurError::MalformedSection {
+                section: SectionId::Header,
+                offset: 5,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn locates_malformed_sections() {
+        // A type section claiming five bytes of which only one is there
+        let error = parse(b"\0asm\x01\0\0\0\x01\x05\x01").err().unwrap();
+        assert_eq!(
+            error.location().map(|(section, _)| section),
+            Some(SectionId::Type)
+        );
+        assert!(matches!(error, KontrolleurError::MalformedSection { .. }));
+    }
+
+    /// A function whose body is a SIMD instruction with an opcode no
+    /// proposal defines yet
+    const UNKNOWN_SIMD: &[u8] = b"\0asm\x01\0\0\0\
+        \x01\x04\x01\x60\0\0\
+        \x03\x02\x01\0\
+        \x0a\x08\x01\x06\0\xfd\xff\xff\x03\x0b";
+
+    #[test]
+    fn names_the_proposal_of_unknown_instructions() {
+        let offset = UNKNOWN_SIMD.iter().position(|&b| b == 0xfd).unwrap();
+        match crate::Analyzer::new().analyze_bytes(UNKNOWN_SIMD) {
+            Err(KontrolleurError::UnsupportedProposal {
+                proposal,
+                section,
+                offset: found,
+            }) => {
+                assert_eq!(proposal, Proposal::Simd);
+                assert_eq!(section, SectionId::Code);
+                assert_eq!(found, offset);
+            }
+            other => panic!("unexpected result {:?}", other.map(drop)),
+        }
+    }
+
+    #[test]
+    fn reads_leb128() {
+        assert_eq!(read_var_u32(&[0x05]), Some((5, 1)));
+        assert_eq!(read_var_u32(&[0xe5, 0x8e, 0x26]), Some((624_485, 3)));
+        assert_eq!(read_var_u32(&[0x80, 0x80]), None);
+    }
+}
@@ -0,0 +1,425 @@
+use crate::{profile, Assumptions, Import};
+use std::{error, fmt};
+
+/// Rules deciding which imports a binary is allowed to have, usually read
+/// from a `kontrolleur.toml` file:
+///
+/// ```toml
+/// [categories]
+/// allow = ["environment", "file_system"]
+/// deny = ["network"]
+///
+/// [imports]
+/// allow = ["wasi_snapshot_preview1::proc_exit"]
+/// deny = ["wasi_snapshot_preview1::path_*", "env::*"]
+/// ```
+///
+/// Categories are the ones of [`Assumptions::categorized`]. Import rules are
+/// written as `module::field`, or just `field` to match any module, and may
+/// use `*` as a wildcard.
+///
+/// An import denied by name is always a violation, and an import allowed by
+/// name never is. Otherwise its category decides: denied categories are
+/// violations. When either allow list is given, only the imports it allows
+/// are accepted: an import has to be allowed by name or by its category.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct Policy {
+    pub categories: Rules,
+    pub imports: Rules,
+}
+
+/// The allow and deny lists for either categories or import names
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct Rules {
+    /// When present, only what is on the list is allowed, along with what
+    /// the allow list of the other kind of rule allows
+    pub allow: Option<Vec<String>>,
+    pub deny: Vec<String>,
+}
+
+/// The rule an import broke
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    /// The import matched the given pattern in `imports.deny`
+    DeniedImport(String),
+    /// The import's category is listed in `categories.deny`
+    DeniedCategory(String),
+    /// The import's category is missing from `categories.allow`, and the
+    /// import from `imports.allow` if given
+    CategoryNotAllowed,
+    /// The import is missing from `imports.allow`, and no `categories.allow`
+    /// is given
+    ImportNotAllowed,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rule::DeniedImport(pattern) => write!(f, "imports.deny \"{}\"", pattern),
+            Rule::DeniedCategory(category) => write!(f, "categories.deny \"{}\"", category),
+            Rule::CategoryNotAllowed => write!(f, "categories.allow"),
+            Rule::ImportNotAllowed => write!(f, "imports.allow"),
+        }
+    }
+}
+
+/// An import the policy does not allow
+#[derive(Debug, Clone)]
+pub struct Violation<'a> {
+    pub import: &'a Import,
+    pub category: String,
+    pub rule: Rule,
+}
+
+impl fmt::Display for Violation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}::{} ({}) violates {}",
+            self.import.module, self.import.field, self.category, self.rule
+        )
+    }
+}
+
+/// Why a policy could not be read
+#[derive(Debug)]
+pub enum PolicyError {
+    /// The file is not valid TOML, or has fields a policy does not have
+    #[cfg(feature = "serde")]
+    Toml(toml::de::Error),
+    /// A category rule names a category no import can be in
+    UnknownCategory(String),
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "serde")]
+            PolicyError::Toml(e) => e.fmt(f),
+            PolicyError::UnknownCategory(category) => write!(
+                f,
+                "unknown category \"{}\", the categories of profiles are prefixed with the profile, as in near.storage",
+                category
+            ),
+        }
+    }
+}
+
+impl error::Error for PolicyError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "serde")]
+            PolicyError::Toml(e) => Some(e),
+            PolicyError::UnknownCategory(_) => None,
+        }
+    }
+}
+
+/// The categories outside of profiles
+const CATEGORIES: &[&str] = &[
+    "file_system",
+    "environment",
+    "process",
+    "network",
+    "memory",
+    "table",
+    "unknown",
+];
+
+impl Policy {
+    /// Read a policy from the contents of a `kontrolleur.toml` file,
+    /// rejecting rules for categories that do not exist
+    #[cfg(feature = "serde")]
+    pub fn from_toml(contents: &str) -> Result<Policy, PolicyError> {
+        let policy: Policy = toml::from_str(contents).map_err(PolicyError::Toml)?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    /// Check that every category the rules name exists, since a misspelled
+    /// or unprefixed category would never match
+    pub fn validate(&self) -> Result<(), PolicyError> {
+        let rules = &self.categories;
+        let mut categories = rules.allow.iter().flatten().chain(&rules.deny);
+        match categories.find(|c| !CATEGORIES.contains(&c.as_str()) && !profile::is_category(c)) {
+            Some(category) => Err(PolicyError::UnknownCategory(category.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Every import in `assumptions` the policy does not allow, in the
+    /// order the report lists them
+    pub fn check<'a>(&self, assumptions: &'a Assumptions) -> Vec<Violation<'a>> {
+        assumptions
+            .categorized()
+            .into_iter()
+            .filter_map(|(category, import)| {
+                self.rule_broken(&category, import).map(|rule| Violation {
+                    import,
+                    category,
+                    rule,
+                })
+            })
+            .collect()
+    }
+
+    fn rule_broken(&self, category: &str, import: &Import) -> Option<Rule> {
+        if let Some(pattern) = self.imports.deny.iter().find(|p| matches_import(p, import)) {
+            return Some(Rule::DeniedImport(pattern.clone()));
+        }
+        let mut allowed = self.imports.allow.iter().flatten();
+        if allowed.any(|p| matches_import(p, import)) {
+            return None;
+        }
+        if self.categories.deny.iter().any(|c| c == category) {
+            return Some(Rule::DeniedCategory(category.to_owned()));
+        }
+        match (&self.categories.allow, &self.imports.allow) {
+            (Some(allowed), _) if allowed.iter().any(|c| c == category) => None,
+            (Some(_), _) => Some(Rule::CategoryNotAllowed),
+            (None, Some(_)) => Some(Rule::ImportNotAllowed),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Whether `import` matches an import rule such as `module::field`
+pub(crate) fn matches_import(pattern: &str, import: &Import) -> bool {
+    match pattern.find("::") {
+        Some(i) => glob(&pattern[..i], &import.module) && glob(&pattern[i + 2..], &import.field),
+        None => glob(pattern, &import.field),
+    }
+}
+
+/// Match `text` against `pattern`, where `*` stands for any run of characters
+fn glob(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let mut rest = match text.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<_> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some(split) => split,
+        // No wildcard at all, the pattern has to match exactly
+        None => return rest.is_empty(),
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, import};
+
+    #[test]
+    fn glob_matches_wildcards_anywhere() {
+        assert!(glob("fd_write", "fd_write"));
+        assert!(!glob("fd_write", "fd_write2"));
+        assert!(!glob("fd_write", "fd_wri"));
+        assert!(glob("*", ""));
+        assert!(glob("path_*", "path_open"));
+        assert!(!glob("path_*", "fd_open"));
+        assert!(glob("*_get", "args_get"));
+        assert!(!glob("*_get", "args_get_more"));
+        assert!(glob("fd_*_get", "fd_fdstat_get"));
+        assert!(glob("fd_*_*_get", "fd_filestat_set_get"));
+        assert!(!glob("fd_*_*_get", "fd_get"));
+        // The suffix may not reuse the characters of the prefix
+        assert!(!glob("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn import_rules_match_module_and_field() {
+        let exit = import("wasi_snapshot_preview1", "proc_exit");
+        assert!(matches_import("proc_exit", &exit));
+        assert!(matches_import("wasi_snapshot_preview1::proc_exit", &exit));
+        assert!(matches_import("wasi_*::proc_*", &exit));
+        assert!(matches_import("*::*", &exit));
+        assert!(!matches_import("env::proc_exit", &exit));
+        assert!(!matches_import("wasi_snapshot_preview1", &exit));
+    }
+
+    #[test]
+    fn import_rules_take_precedence_over_categories() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+                (import "wasi_snapshot_preview1" "path_open"
+                    (func (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+                (import "wasi_snapshot_preview1" "sock_send"
+                    (func (param i32 i32 i32 i32 i32) (result i32)))
+                (import "env" "log" (func (param i32))))"#,
+        );
+        let policy = Policy {
+            categories: Rules {
+                allow: Some(vec!["file_system".to_owned(), "network".to_owned()]),
+                deny: vec!["network".to_owned()],
+            },
+            imports: Rules {
+                allow: Some(vec!["proc_exit".to_owned()]),
+                deny: vec!["wasi_snapshot_preview1::path_*".to_owned()],
+            },
+        };
+        let violations: Vec<_> = policy
+            .check(&report.assumptions)
+            .into_iter()
+            .map(|v| (v.import.field.as_str(), v.category, v.rule))
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                (
+                    "path_open",
+                    "file_system".to_owned(),
+                    Rule::DeniedImport("wasi_snapshot_preview1::path_*".to_owned())
+                ),
+                (
+                    "sock_send",
+                    "network".to_owned(),
+                    Rule::DeniedCategory("network".to_owned())
+                ),
+                ("log", "unknown".to_owned(), Rule::CategoryNotAllowed),
+            ]
+        );
+    }
+
+    #[test]
+    fn profile_categories_are_prefixed_with_the_profile() {
+        let report = analyze(
+            r#"(module
+                (import "env" "storage_write"
+                    (func (param i64 i64 i64 i64 i64) (result i64)))
+                (import "env" "panic_utf8" (func (param i64 i64)))
+                (func (export "set")))"#,
+        );
+        let policy = Policy {
+            categories: Rules {
+                allow: None,
+                deny: vec!["near.storage".to_owned(), "storage".to_owned()],
+            },
+            imports: Rules::default(),
+        };
+        let violations: Vec<_> = policy
+            .check(&report.assumptions)
+            .into_iter()
+            .map(|v| (v.import.field.as_str(), v.category))
+            .collect();
+        assert_eq!(
+            violations,
+            vec![("storage_write", "near.storage".to_owned())]
+        );
+    }
+
+    #[test]
+    fn import_allow_lists_reject_other_imports() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+                (import "wasi_snapshot_preview1" "sched_yield" (func (result i32)))
+                (import "wasi_snapshot_preview1" "sock_send"
+                    (func (param i32 i32 i32 i32 i32) (result i32))))"#,
+        );
+        let mut policy = Policy {
+            categories: Rules::default(),
+            imports: Rules {
+                allow: Some(vec!["proc_exit".to_owned()]),
+                deny: Vec::new(),
+            },
+        };
+        let violations = |policy: &Policy| -> Vec<_> {
+            policy
+                .check(&report.assumptions)
+                .into_iter()
+                .map(|v| (v.import.field.clone(), v.rule))
+                .collect()
+        };
+        assert_eq!(
+            violations(&policy),
+            vec![
+                ("sched_yield".to_owned(), Rule::ImportNotAllowed),
+                ("sock_send".to_owned(), Rule::ImportNotAllowed),
+            ]
+        );
+
+        // An import is accepted when either allow list allows it
+        policy.categories.allow = Some(vec!["process".to_owned()]);
+        assert_eq!(
+            violations(&policy),
+            vec![("sock_send".to_owned(), Rule::CategoryNotAllowed)]
+        );
+        assert_eq!(
+            Rule::ImportNotAllowed.to_string(),
+            "imports.allow".to_owned()
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_categories() {
+        let policy = |allow: &[&str], deny: &[&str]| Policy {
+            categories: Rules {
+                allow: Some(allow.iter().map(|c| c.to_string()).collect()),
+                deny: deny.iter().map(|c| c.to_string()).collect(),
+            },
+            imports: Rules::default(),
+        };
+        assert!(
+            policy(&["file_system", "memory", "unknown"], &["near.storage"])
+                .validate()
+                .is_ok()
+        );
+        assert!(policy(&["go.memory_view", "emscripten.heap"], &[])
+            .validate()
+            .is_ok());
+        for category in &["storage", "filesystem", "near.memory", "near.", "nope.io"] {
+            assert!(matches!(
+                policy(&[], &[category]).validate(),
+                Err(PolicyError::UnknownCategory(c)) if c == *category
+            ));
+            assert!(policy(&[category], &[]).validate().is_err());
+        }
+    }
+
+    #[test]
+    fn no_rules_allow_everything() {
+        let report = analyze(r#"(module (import "env" "log" (func)))"#);
+        assert!(Policy::default().check(&report.assumptions).is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn reads_toml() {
+        let policy = Policy::from_toml(
+            r#"
+            [categories]
+            deny = ["network"]
+
+            [imports]
+            allow = ["proc_exit"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(policy.categories.deny, vec!["network"]);
+        assert!(policy.categories.allow.is_none());
+        assert_eq!(policy.imports.allow, Some(vec!["proc_exit".to_owned()]));
+        assert!(matches!(
+            Policy::from_toml("[imports]\nblock = []"),
+            Err(PolicyError::Toml(_))
+        ));
+        assert!(matches!(
+            Policy::from_toml("[categories]\ndeny = [\"storage\"]"),
+            Err(PolicyError::UnknownCategory(c)) if c == "storage"
+        ));
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{callgraph::CallGraph, module::ParsedModule, Import, Reachability, Signature};
+use std::fmt;
+use wasmparser::{ExternalKind, TypeRef};
+
+/// The imports of a binary that follow the conventions of a toolchain or
+/// host, grouped by category like the calls of [`WasiAssumptions`].
+///
+/// [`WasiAssumptions`]: crate::WasiAssumptions
+pub trait Profile {
+    /// The identifier of the profile, as used in the JSON output
+    fn id(&self) -> &'static str;
+
+    /// The name of the toolchain or host, as shown in the report
+    fn name(&self) -> &'static str;
+
+    /// The imports of the profile, grouped by category. Categories without
+    /// imports are included as well.
+    fn categories(&self) -> Vec<(&'static str, &[Import])>;
+
+    /// Imports that belong to the profile, such as those of a module only
+    /// its runtime provides, but that fit none of its categories
+    fn unknown(&self) -> &[Import] {
+        &[]
+    }
+
+    /// What else the report should say about the binary, such as how it was
+    /// built, one sentence each
+    fn notes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Ways the binary breaks the rules of the toolchain or host that keep
+    /// it from loading, one sentence each
+    fn errors(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// The categories each exported method of the binary needs, for hosts
+    /// that call binaries by their exports
+    fn entry_points(&self) -> &[EntryPoint] {
+        &[]
+    }
+
+    fn count(&self) -> usize {
+        let categorized: usize = self
+            .categories()
+            .iter()
+            .map(|(_, imports)| imports.len())
+            .sum();
+        categorized + self.unknown().len()
+    }
+}
+
+/// The categories of every profile, by the id of the profile, leaving out
+/// the ones it shares with WASI. [`Assumptions::categorized`] prefixes them
+/// with the id, and policies are checked against them.
+///
+/// [`Assumptions::categorized`]: crate::Assumptions::categorized
+pub(crate) const CATEGORIES: &[(&str, &[&str])] = &[
+    (
+        "emscripten",
+        &[
+            "heap",
+            "dynamic_linking",
+            "exception_emulation",
+            "js_interop",
+        ],
+    ),
+    (
+        "wasm_bindgen",
+        &[
+            "dom",
+            "fetch",
+            "timers",
+            "console",
+            "crypto",
+            "js_interop",
+            "intrinsics",
+        ],
+    ),
+    (
+        "go",
+        &["js_interop", "timer", "memory_view", "io", "random"],
+    ),
+    (
+        "assemblyscript",
+        &["tracing", "randomness", "time", "js_interop"],
+    ),
+    (
+        "proxy_wasm",
+        &[
+            "headers",
+            "http_calls",
+            "shared_data",
+            "metrics",
+            "timers",
+            "logging",
+            "properties",
+            "stream",
+        ],
+    ),
+    (
+        "fastly",
+        &[
+            "backend_requests",
+            "http",
+            "kv",
+            "config_store",
+            "secret_store",
+            "logging",
+            "geo",
+            "cache",
+            "platform",
+        ],
+    ),
+    (
+        "extism",
+        &["http", "variables", "config", "logging", "custom", "kernel"],
+    ),
+    (
+        "cosmwasm",
+        &["storage", "addresses", "crypto", "queries", "debug"],
+    ),
+    (
+        "near",
+        &["storage", "promises", "account", "crypto", "logging", "io"],
+    ),
+    (
+        "ic0",
+        &[
+            "messaging",
+            "calls",
+            "stable_memory",
+            "certification",
+            "cycles",
+            "time",
+            "canister",
+            "debug",
+        ],
+    ),
+    (
+        "seal",
+        &[
+            "storage",
+            "calls",
+            "balance",
+            "crypto",
+            "chain_extensions",
+            "context",
+            "events",
+            "io",
+            "debug",
+        ],
+    ),
+    (
+        "stylus",
+        &[
+            "storage",
+            "external_calls",
+            "logs",
+            "context",
+            "crypto",
+            "math",
+            "io",
+        ],
+    ),
+];
+
+/// Whether `category`, prefixed with the id of its profile like
+/// `near.storage`, is a category of a profile
+pub(crate) fn is_category(category: &str) -> bool {
+    let (id, category) = match category.split_once('.') {
+        Some(split) => split,
+        None => return false,
+    };
+    CATEGORIES
+        .iter()
+        .any(|(profile, categories)| *profile == id && categories.contains(&category))
+}
+
+/// An export whose type differs from the one the toolchain or host expects
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExportMismatch {
+    pub export: String,
+    pub signature: Signature,
+    pub expected: Signature,
+}
+
+impl fmt::Display for ExportMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is exported as {} but is expected to be {}",
+            self.export, self.signature, self.expected
+        )
+    }
+}
+
+/// An exported function and the categories of the calls it makes
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntryPoint {
+    pub export: String,
+    /// The categories of the calls made through chains of direct calls
+    pub categories: Vec<String>,
+    /// The other categories of calls, which the function can only make
+    /// through `call_indirect`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub indirect_categories: Vec<String>,
+}
+
+/// The entry points of every function export, following the calls like
+/// `--reachability` does. `category` names the
+/// category of an imported function, given its module and name, or `None`
+/// for imports outside the profile. Categories are listed in the order of
+/// `order`.
+pub(crate) fn entry_points(
+    module: &ParsedModule,
+    order: &[&str],
+    category: impl Fn(&str, &str) -> Option<&'static str>,
+) -> Vec<EntryPoint> {
+    let graph = CallGraph::new(module);
+    let imported: Vec<_> = module
+        .imports
+        .iter()
+        .filter(|i| matches!(i.ty, TypeRef::Func(_) | TypeRef::FuncExact(_)))
+        .map(|i| category(i.module, i.name))
+        .collect();
+    module
+        .exports
+        .iter()
+        .filter(|e| e.kind == ExternalKind::Func)
+        .map(|export| {
+            let reachability = graph.import_reachability_from(&[export.index]);
+            let used = |wanted: Reachability| -> Vec<_> {
+                imported
+                    .iter()
+                    .zip(&reachability)
+                    .filter_map(|(category, &r)| category.filter(|_| r == wanted))
+                    .collect()
+            };
+            let (direct, indirect) = (
+                used(Reachability::Reachable),
+                used(Reachability::IndirectOnly),
+            );
+            let ordered = |used: &[&str]| {
+                order
+                    .iter()
+                    .filter(|c| used.contains(c))
+                    .map(|c| c.to_string())
+                    .collect::<Vec<_>>()
+            };
+            let categories = ordered(&direct);
+            let indirect_categories = ordered(&indirect)
+                .into_iter()
+                .filter(|c| !categories.contains(c))
+                .collect();
+            EntryPoint {
+                export: export.name.to_owned(),
+                categories,
+                indirect_categories,
+            }
+        })
+        .collect()
+}
+
+/// Sorts the imports a profile recognizes into its categories
+pub(crate) trait Classifier {
+    /// Take the import if it belongs to the profile, or hand it back
+    fn classify(&mut self, import: Import) -> Result<(), Import>;
+}
@@ -0,0 +1,717 @@
+use crate::{parse, KontrolleurError, SectionId};
+use std::fmt;
+use wasmparser::{
+    AbstractHeapType, BlockType, CompositeInnerType, ElementItems, ElementKind, HeapType,
+    MemoryType, Operator, Parser, Payload, RefType, TableType, TypeRef, ValType,
+};
+
+/// A post-MVP WebAssembly proposal a binary can rely on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Proposal {
+    SignExtension,
+    SaturatingFloatToInt,
+    MultiValue,
+    BulkMemory,
+    ReferenceTypes,
+    Simd,
+    RelaxedSimd,
+    Threads,
+    TailCalls,
+    ExceptionHandling,
+    Memory64,
+    MultiMemory,
+    FunctionReferences,
+    Gc,
+    WideArithmetic,
+    StackSwitching,
+    MemoryControl,
+}
+
+impl Proposal {
+    /// The identifier of the proposal, as used in the JSON output
+    pub fn name(self) -> &'static str {
+        match self {
+            Proposal::SignExtension => "sign_extension",
+            Proposal::SaturatingFloatToInt => "saturating_float_to_int",
+            Proposal::MultiValue => "multi_value",
+            Proposal::BulkMemory => "bulk_memory",
+            Proposal::ReferenceTypes => "reference_types",
+            Proposal::Simd => "simd",
+            Proposal::RelaxedSimd => "relaxed_simd",
+            Proposal::Threads => "threads",
+            Proposal::TailCalls => "tail_calls",
+            Proposal::ExceptionHandling => "exception_handling",
+            Proposal::Memory64 => "memory64",
+            Proposal::MultiMemory => "multi_memory",
+            Proposal::FunctionReferences => "function_references",
+            Proposal::Gc => "gc",
+            Proposal::WideArithmetic => "wide_arithmetic",
+            Proposal::StackSwitching => "stack_switching",
+            Proposal::MemoryControl => "memory_control",
+        }
+    }
+}
+
+impl fmt::Display for Proposal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Proposal::SignExtension => "sign extension",
+            Proposal::SaturatingFloatToInt => "non-trapping float-to-int conversions",
+            Proposal::MultiValue => "multi-value",
+            Proposal::BulkMemory => "bulk memory",
+            Proposal::ReferenceTypes => "reference types",
+            Proposal::Simd => "SIMD",
+            Proposal::RelaxedSimd => "relaxed SIMD",
+            Proposal::Threads => "threads",
+            Proposal::TailCalls => "tail calls",
+            Proposal::ExceptionHandling => "exception handling",
+            Proposal::Memory64 => "memory64",
+            Proposal::MultiMemory => "multi-memory",
+            Proposal::FunctionReferences => "typed function references",
+            Proposal::Gc => "GC",
+            Proposal::WideArithmetic => "wide arithmetic",
+            Proposal::StackSwitching => "stack switching",
+            Proposal::MemoryControl => "memory control",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The first place a binary relies on a proposal
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProposalUse {
+    pub proposal: Proposal,
+    pub section: SectionId,
+    pub offset: usize,
+    /// The index of the function using the proposal, for uses in code
+    pub function: Option<u32>,
+    /// The name of that function, if the binary has a name section
+    pub function_name: Option<String>,
+}
+
+macro_rules! proposal {
+    (mvp) => {
+        None
+    };
+    (sign_extension) => {
+        Some(Proposal::SignExtension)
+    };
+    (saturating_float_to_int) => {
+        Some(Proposal::SaturatingFloatToInt)
+    };
+    (bulk_memory) => {
+        Some(Proposal::BulkMemory)
+    };
+    (reference_types) => {
+        Some(Proposal::ReferenceTypes)
+    };
+    (simd) => {
+        Some(Proposal::Simd)
+    };
+    (relaxed_simd) => {
+        Some(Proposal::RelaxedSimd)
+    };
+    (threads) => {
+        Some(Proposal::Threads)
+    };
+    (shared_everything_threads) => {
+        Some(Proposal::Threads)
+    };
+    (tail_call) => {
+        Some(Proposal::TailCalls)
+    };
+    (exceptions) => {
+        Some(Proposal::ExceptionHandling)
+    };
+    (legacy_exceptions) => {
+        Some(Proposal::ExceptionHandling)
+    };
+    (function_references) => {
+        Some(Proposal::FunctionReferences)
+    };
+    (gc) => {
+        Some(Proposal::Gc)
+    };
+    (custom_descriptors) => {
+        Some(Proposal::Gc)
+    };
+    (wide_arithmetic) => {
+        Some(Proposal::WideArithmetic)
+    };
+    (stack_switching) => {
+        Some(Proposal::StackSwitching)
+    };
+    (memory_control) => {
+        Some(Proposal::MemoryControl)
+    };
+}
+
+macro_rules! define_operator_proposal {
+    ($( @$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident ($($ann:tt)*) )*) => {
+        /// The proposal that introduced an operator, or `None` for MVP operators
+        pub(crate) fn operator_proposal(operator: &Operator) -> Option<Proposal> {
+            match operator {
+                $( Operator::$op { .. } => proposal!($proposal), )*
+                _ => None,
+            }
+        }
+    };
+}
+
+wasmparser::for_each_operator!(define_operator_proposal);
+
+macro_rules! define_operator_name {
+    ($( @$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident ($($ann:tt)*) )*) => {
+        /// The name of an operator as wasmparser spells it, like `F32Add`
+        pub(crate) fn operator_name(operator: &Operator) -> &'static str {
+            match operator {
+                $( Operator::$op { .. } => stringify!($op), )*
+                _ => "unknown",
+            }
+        }
+    };
+}
+
+wasmparser::for_each_operator!(define_operator_name);
+
+/// The name of an operator in the text format, like `f32.add` for `F32Add`
+pub(crate) fn instruction_name(operator: &Operator) -> String {
+    let mut words = Vec::new();
+    for (i, c) in operator_name(operator).char_indices() {
+        if c.is_ascii_uppercase() || i == 0 {
+            words.push(String::new());
+        }
+        if let Some(word) = words.last_mut() {
+            word.push(c.to_ascii_lowercase());
+        }
+    }
+    // Most operators start with the type or the kind of item they work on,
+    // followed by a dot
+    let namespaced = match words.split_first() {
+        Some((first, rest)) if !rest.is_empty() => {
+            first.chars().nth(1).is_some_and(|c| c.is_ascii_digit())
+                || NAMESPACES.contains(&first.as_str())
+        }
+        _ => false,
+    };
+    if !namespaced {
+        return words.join("_");
+    }
+    let mut name = String::new();
+    for (i, word) in words.iter().enumerate() {
+        name.push_str(word);
+        if i + 1 < words.len() {
+            let dot = i == 0 || word == "atomic" || word.starts_with("rmw");
+            name.push(if dot { '.' } else { '_' });
+        }
+    }
+    name
+}
+
+/// The kinds of items whose operators are named like `memory.grow`
+const NAMESPACES: &[&str] = &[
+    "local", "global", "memory", "table", "ref", "data", "elem", "atomic", "struct", "array",
+    "any", "extern",
+];
+
+/// Whether an operator computes with or converts from or to floats
+pub(crate) fn is_float_operator(operator: &Operator) -> bool {
+    let name = operator_name(operator);
+    name.contains("F32") || name.contains("F64")
+}
+
+/// The result of scanning a binary for proposals
+pub(crate) struct Scan {
+    /// The first use of every proposal, in the order they appear in the binary
+    pub(crate) uses: Vec<ProposalUse>,
+    /// The error that stopped the scan early, if any
+    pub(crate) error: Option<KontrolleurError>,
+}
+
+/// Find the first use of every proposal in `bytes`
+pub(crate) fn scan(bytes: &[u8]) -> Scan {
+    let mut scanner = Scanner {
+        uses: Vec::new(),
+        section: SectionId::Header,
+        function: None,
+        functions: 0,
+        tables: 0,
+        memories: 0,
+    };
+    let error = scanner
+        .scan(bytes)
+        .err()
+        .map(|e| parse::malformed(bytes, e));
+    Scan {
+        uses: scanner.uses,
+        error,
+    }
+}
+
+struct Scanner {
+    uses: Vec<ProposalUse>,
+    section: SectionId,
+    /// The function whose body is being scanned
+    function: Option<u32>,
+    /// The number of functions seen so far, imported or defined
+    functions: u32,
+    tables: u32,
+    memories: u32,
+}
+
+impl Scanner {
+    fn scan(&mut self, bytes: &[u8]) -> wasmparser::Result<()> {
+        for payload in Parser::new(0).parse_all(bytes) {
+            let payload = payload?;
+            if let Some((id, _)) = payload.as_section() {
+                self.section = SectionId::from_id(id);
+                self.function = None;
+            }
+            match payload {
+                Payload::TypeSection(reader) => {
+                    for group in reader.into_iter_with_offsets() {
+                        let (offset, group) = group?;
+                        if group.is_explicit_rec_group() {
+                            self.note(Proposal::Gc, offset);
+                        }
+                        for ty in group.types() {
+                            if !ty.is_final || ty.supertype_idx.is_some() {
+                                self.note(Proposal::Gc, offset);
+                            }
+                            match &ty.composite_type.inner {
+                                CompositeInnerType::Func(f) => {
+                                    if f.results().len() > 1 {
+                                        self.note(Proposal::MultiValue, offset);
+                                    }
+                                    for &ty in f.params().iter().chain(f.results()) {
+                                        self.value_type(ty, offset);
+                                    }
+                                }
+                                CompositeInnerType::Cont(_) => {
+                                    self.note(Proposal::StackSwitching, offset)
+                                }
+                                _ => self.note(Proposal::Gc, offset),
+                            }
+                        }
+                    }
+                }
+                Payload::ImportSection(reader) => {
+                    for import in reader.into_imports_with_offsets() {
+                        let (offset, import) = import?;
+                        match import.ty {
+                            TypeRef::Func(_) | TypeRef::FuncExact(_) => self.functions += 1,
+                            TypeRef::Table(ty) => self.table(ty, offset),
+                            TypeRef::Memory(ty) => self.memory(ty, offset),
+                            TypeRef::Global(ty) => {
+                                self.value_type(ty.content_type, offset);
+                                if ty.shared {
+                                    self.note(Proposal::Threads, offset);
+                                }
+                            }
+                            TypeRef::Tag(_) => self.note(Proposal::ExceptionHandling, offset),
+                        }
+                    }
+                }
+                Payload::TableSection(reader) => {
+                    for table in reader.into_iter_with_offsets() {
+                        let (offset, table) = table?;
+                        self.table(table.ty, offset);
+                    }
+                }
+                Payload::MemorySection(reader) => {
+                    for memory in reader.into_iter_with_offsets() {
+                        let (offset, memory) = memory?;
+                        self.memory(memory, offset);
+                    }
+                }
+                Payload::TagSection(reader) => {
+                    self.note(Proposal::ExceptionHandling, reader.range().start)
+                }
+                Payload::GlobalSection(reader) => {
+                    for global in reader.into_iter_with_offsets() {
+                        let (offset, global) = global?;
+                        self.value_type(global.ty.content_type, offset);
+                        if global.ty.shared {
+                            self.note(Proposal::Threads, offset);
+                        }
+                    }
+                }
+                Payload::ElementSection(reader) => {
+                    for element in reader.into_iter_with_offsets() {
+                        let (offset, element) = element?;
+                        match element.kind {
+                            ElementKind::Passive => self.note(Proposal::BulkMemory, offset),
+                            ElementKind::Declared => self.note(Proposal::ReferenceTypes, offset),
+                            ElementKind::Active { .. } => {}
+                        }
+                        if let ElementItems::Expressions(..) = element.items {
+                            self.note(Proposal::ReferenceTypes, offset);
+                        }
+                    }
+                }
+                Payload::DataSection(reader) => {
+                    for data in reader.into_iter_with_offsets() {
+                        let (offset, data) = data?;
+                        match data.kind {
+                            wasmparser::DataKind::Passive => {
+                                self.note(Proposal::BulkMemory, offset)
+                            }
+                            wasmparser::DataKind::Active { memory_index, .. } => {
+                                if memory_index != 0 {
+                                    self.note(Proposal::MultiMemory, offset);
+                                }
+                            }
+                        }
+                    }
+                }
+                Payload::CodeSectionEntry(body) => {
+                    self.function = Some(self.functions);
+                    self.functions += 1;
+                    let mut locals = body.get_locals_reader()?;
+                    for _ in 0..locals.get_count() {
+                        let offset = locals.original_position();
+                        let (_, ty) = locals.read()?;
+                        self.value_type(ty, offset);
+                    }
+                    let mut operators = body.get_operators_reader()?;
+                    while !operators.eof() {
+                        let (operator, offset) = operators.read_with_offset()?;
+                        if let Some(proposal) = operator_proposal(&operator) {
+                            self.note(proposal, offset);
+                        }
+                        match operator {
+                            Operator::Block { blockty }
+                            | Operator::Loop { blockty }
+                            | Operator::If { blockty } => {
+                                if let BlockType::FuncType(_) = blockty {
+                                    self.note(Proposal::MultiValue, offset);
+                                }
+                            }
+                            Operator::TypedSelect { ty } => self.value_type(ty, offset),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn note(&mut self, proposal: Proposal, offset: usize) {
+        if self.uses.iter().all(|u| u.proposal != proposal) {
+            self.uses.push(ProposalUse {
+                proposal,
+                section: self.section,
+                offset,
+                function: self.function,
+                function_name: None,
+            });
+        }
+    }
+
+    fn value_type(&mut self, ty: ValType, offset: usize) {
+        match ty {
+            ValType::V128 => self.note(Proposal::Simd, offset),
+            ValType::Ref(ty) => self.ref_type(ty, offset),
+            _ => {}
+        }
+    }
+
+    fn ref_type(&mut self, ty: RefType, offset: usize) {
+        let proposal = match ty.heap_type() {
+            HeapType::Abstract {
+                ty: AbstractHeapType::Func | AbstractHeapType::Extern,
+                ..
+            } if ty.is_nullable() => Proposal::ReferenceTypes,
+            // Non-nullable references and references to a type of the module
+            // are typed function references. References to struct and array
+            // types are noted as GC where those types are defined.
+            HeapType::Abstract {
+                ty: AbstractHeapType::Func | AbstractHeapType::Extern,
+                ..
+            }
+            | HeapType::Concrete(_) => Proposal::FunctionReferences,
+            HeapType::Abstract {
+                ty: AbstractHeapType::Exn | AbstractHeapType::NoExn,
+                ..
+            } => Proposal::ExceptionHandling,
+            HeapType::Abstract {
+                ty: AbstractHeapType::Cont | AbstractHeapType::NoCont,
+                ..
+            } => Proposal::StackSwitching,
+            HeapType::Abstract { .. } | HeapType::Exact(_) => Proposal::Gc,
+        };
+        self.note(proposal, offset);
+    }
+
+    fn table(&mut self, ty: TableType, offset: usize) {
+        self.tables += 1;
+        if self.tables > 1 {
+            self.note(Proposal::ReferenceTypes, offset);
+        }
+        if ty.element_type != RefType::FUNCREF {
+            self.ref_type(ty.element_type, offset);
+        }
+        if ty.table64 {
+            self.note(Proposal::Memory64, offset);
+        }
+    }
+
+    fn memory(&mut self, ty: MemoryType, offset: usize) {
+        self.memories += 1;
+        if self.memories > 1 {
+            self.note(Proposal::MultiMemory, offset);
+        }
+        if ty.memory64 {
+            self.note(Proposal::Memory64, offset);
+        }
+        if ty.shared {
+            self.note(Proposal::Threads, offset);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, wasm};
+
+    /// The first use of `proposal` in the module, checking that its offset
+    /// points at `bytes` when given
+    fn first_use(wat: &str, proposal: Proposal, bytes: Option<&[u8]>) -> ProposalUse {
+        let found = analyze(wat)
+            .proposals
+            .into_iter()
+            .find(|u| u.proposal == proposal)
+            .unwrap_or_else(|| panic!("{} not found", proposal));
+        if let Some(expected) = bytes {
+            assert!(wasm(wat)[found.offset..].starts_with(expected));
+        }
+        found
+    }
+
+    /// Assert that `proposal` is first used in the code of function 0, named
+    /// `f`, by an instruction starting with `opcode`
+    fn assert_instruction(body: &str, proposal: Proposal, opcode: &[u8]) {
+        let wat = format!("(module (memory 1) (type $t (func)) (func $f {}))", body);
+        let found = first_use(&wat, proposal, Some(opcode));
+        assert_eq!(found.section, SectionId::Code);
+        assert_eq!(found.function, Some(0));
+        assert_eq!(found.function_name.as_deref(), Some("f"));
+    }
+
+    /// Assert that `proposal` is first used in `section`, outside of code
+    fn assert_section(wat: &str, proposal: Proposal, section: SectionId) {
+        let found = first_use(wat, proposal, None);
+        assert_eq!(found.section, section);
+        assert_eq!(found.function, None);
+        assert_eq!(found.function_name, None);
+    }
+
+    #[test]
+    fn mvp_modules_use_no_proposals() {
+        let report = analyze(
+            r#"(module
+                (import "env" "f" (func (param i32) (result i32)))
+                (memory 1)
+                (table 1 funcref)
+                (func (result i32) i32.const 1 call 0))"#,
+        );
+        assert!(report.proposals.is_empty());
+    }
+
+    #[test]
+    fn counts_imported_functions_and_names_the_user() {
+        let wat = r#"(module
+            (import "env" "f" (func))
+            (func $plain)
+            (func $narrow (param i32) (result i32) local.get 0 i32.extend8_s))"#;
+        let found = first_use(wat, Proposal::SignExtension, Some(&[0xc0]));
+        assert_eq!(found.section, SectionId::Code);
+        assert_eq!(found.function, Some(2));
+        assert_eq!(found.function_name.as_deref(), Some("narrow"));
+    }
+
+    #[test]
+    fn reports_only_the_first_use() {
+        let wat = r#"(module
+            (func $first (param i32) (result i32) local.get 0 i32.extend8_s)
+            (func $second (param i32) (result i32) local.get 0 i32.extend16_s))"#;
+        let uses = analyze(wat).proposals;
+        assert_eq!(uses.len(), 1);
+        assert_eq!(uses[0].function_name.as_deref(), Some("first"));
+    }
+
+    #[test]
+    fn finds_proposals_in_code() {
+        assert_instruction(
+            "f32.const 0 i32.trunc_sat_f32_s drop",
+            Proposal::SaturatingFloatToInt,
+            &[0xfc, 0x00],
+        );
+        assert_instruction(
+            "i32.const 0 i32.const 0 i32.const 0 memory.fill",
+            Proposal::BulkMemory,
+            &[0xfc, 0x0b],
+        );
+        assert_instruction("ref.null func drop", Proposal::ReferenceTypes, &[0xd0]);
+        assert_instruction("v128.const i64x2 0 0 drop", Proposal::Simd, &[0xfd, 0x0c]);
+        assert_instruction(
+            "v128.const i64x2 0 0 v128.const i64x2 0 0 i8x16.relaxed_swizzle drop",
+            Proposal::RelaxedSimd,
+            &[0xfd, 0x80, 0x02],
+        );
+        assert_instruction("atomic.fence", Proposal::Threads, &[0xfe, 0x03]);
+        assert_instruction("return_call $f", Proposal::TailCalls, &[0x12]);
+        assert_instruction(
+            "ref.func $f call_ref $t",
+            Proposal::FunctionReferences,
+            &[0x14],
+        );
+        assert_instruction(
+            "i64.const 0 i64.const 0 i64.const 0 i64.const 0 i64.add128 drop drop",
+            Proposal::WideArithmetic,
+            &[0xfc, 0x13],
+        );
+    }
+
+    #[test]
+    fn finds_proposals_in_blocks_and_locals() {
+        assert_instruction("(local v128)", Proposal::Simd, &[0x01, 0x7b]);
+        let wat = r#"(module
+            (type $pair (func (result i32 i32)))
+            (func $f (result i32)
+                (block (type $pair) i32.const 1 i32.const 2) drop))"#;
+        let uses = analyze(wat).proposals;
+        // The type is found before the block that uses it
+        assert_eq!(uses[0].proposal, Proposal::MultiValue);
+        assert_eq!(uses[0].section, SectionId::Type);
+    }
+
+    #[test]
+    fn finds_proposals_in_declarations() {
+        assert_section(
+            "(module (func (result i32 i32) i32.const 1 i32.const 2))",
+            Proposal::MultiValue,
+            SectionId::Type,
+        );
+        assert_section(
+            "(module (func (param externref)))",
+            Proposal::ReferenceTypes,
+            SectionId::Type,
+        );
+        assert_section(
+            "(module (table 1 funcref) (table 1 funcref))",
+            Proposal::ReferenceTypes,
+            SectionId::Table,
+        );
+        assert_section(
+            r#"(module (memory 1) (data "passive"))"#,
+            Proposal::BulkMemory,
+            SectionId::Data,
+        );
+        assert_section(
+            "(module (memory 1 1 shared))",
+            Proposal::Threads,
+            SectionId::Memory,
+        );
+        assert_section(
+            r#"(module (import "env" "memory" (memory 1 1 shared)))"#,
+            Proposal::Threads,
+            SectionId::Import,
+        );
+        assert_section(
+            "(module (tag))",
+            Proposal::ExceptionHandling,
+            SectionId::Tag,
+        );
+        assert_section(
+            "(module (memory i64 1))",
+            Proposal::Memory64,
+            SectionId::Memory,
+        );
+        assert_section(
+            "(module (memory 1) (memory 1))",
+            Proposal::MultiMemory,
+            SectionId::Memory,
+        );
+        assert_section(
+            "(module (type (struct (field i32))))",
+            Proposal::Gc,
+            SectionId::Type,
+        );
+        assert_section(
+            "(module (type $f (func)) (type (cont $f)))",
+            Proposal::StackSwitching,
+            SectionId::Type,
+        );
+    }
+
+    #[test]
+    fn typed_function_references_are_not_gc() {
+        for wat in &[
+            "(module (type $t (func)) (func (param (ref $t))))",
+            "(module (type $t (func)) (func (param (ref null $t))))",
+            "(module (func (param (ref func))))",
+        ] {
+            assert_section(wat, Proposal::FunctionReferences, SectionId::Type);
+            assert!(analyze(wat)
+                .proposals
+                .iter()
+                .all(|u| u.proposal != Proposal::Gc));
+        }
+        assert_section(
+            "(module (func (param exnref)))",
+            Proposal::ExceptionHandling,
+            SectionId::Type,
+        );
+        assert_section(
+            "(module (func (param (ref null any))))",
+            Proposal::Gc,
+            SectionId::Type,
+        );
+    }
+
+    #[test]
+    fn names_instructions_in_the_text_format() {
+        let cases = [
+            (Operator::F32Add, "f32.add", true),
+            (Operator::I64TruncSatF64U, "i64.trunc_sat_f64_u", true),
+            (Operator::F64ConvertI32S, "f64.convert_i32_s", true),
+            (Operator::I32Add, "i32.add", false),
+            (Operator::I32WrapI64, "i32.wrap_i64", false),
+            (Operator::Nop, "nop", false),
+        ];
+        for (operator, name, float) in cases {
+            assert_eq!(instruction_name(&operator), name);
+            assert_eq!(is_float_operator(&operator), float, "{}", name);
+        }
+    }
+
+    #[test]
+    fn names_namespaced_instructions() {
+        let cases = [
+            (Operator::MemoryGrow { mem: 0 }, "memory.grow"),
+            (Operator::LocalGet { local_index: 0 }, "local.get"),
+            (Operator::AtomicFence, "atomic.fence"),
+            (
+                Operator::I64AtomicRmw8AddU {
+                    memarg: wasmparser::MemArg {
+                        align: 0,
+                        max_align: 0,
+                        offset: 0,
+                        memory: 0,
+                    },
+                },
+                "i64.atomic.rmw8.add_u",
+            ),
+        ];
+        for (operator, name) in cases {
+            assert_eq!(instruction_name(&operator), name);
+        }
+    }
+}
@@ -0,0 +1,458 @@
+use crate::{
+    module::ParsedModule, profile::Classifier, ExportMismatch, Import, Profile, Signature,
+    ValueType,
+};
+
+/// The version of the proxy-wasm ABI a filter declares by exporting
+/// `proxy_abi_version_*`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProxyWasmAbi {
+    #[cfg_attr(feature = "serde", serde(rename = "0.1.0"))]
+    V0_1_0,
+    #[cfg_attr(feature = "serde", serde(rename = "0.2.0"))]
+    V0_2_0,
+    #[cfg_attr(feature = "serde", serde(rename = "0.2.1"))]
+    V0_2_1,
+}
+
+impl ProxyWasmAbi {
+    fn from_export(name: &str) -> Option<ProxyWasmAbi> {
+        match name {
+            "proxy_abi_version_0_1_0" => Some(ProxyWasmAbi::V0_1_0),
+            "proxy_abi_version_0_2_0" => Some(ProxyWasmAbi::V0_2_0),
+            "proxy_abi_version_0_2_1" => Some(ProxyWasmAbi::V0_2_1),
+            _ => None,
+        }
+    }
+
+    pub fn version(self) -> &'static str {
+        match self {
+            ProxyWasmAbi::V0_1_0 => "0.1.0",
+            ProxyWasmAbi::V0_2_0 => "0.2.0",
+            ProxyWasmAbi::V0_2_1 => "0.2.1",
+        }
+    }
+}
+
+/// The host calls a proxy-wasm filter makes into Envoy or another proxy,
+/// grouped by what they give access to
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProxyWasmAssumptions {
+    /// The ABI version the filter declares, if it declares one
+    pub abi: Option<ProxyWasmAbi>,
+    /// The exports the host needs but the filter lacks
+    pub missing_exports: Vec<String>,
+    pub export_mismatches: Vec<ExportMismatch>,
+    /// Host calls the declared ABI version does not define
+    pub disallowed: Vec<Import>,
+    pub headers: Vec<Import>,
+    pub http_calls: Vec<Import>,
+    pub shared_data: Vec<Import>,
+    pub metrics: Vec<Import>,
+    pub timers: Vec<Import>,
+    pub logging: Vec<Import>,
+    pub properties: Vec<Import>,
+    /// Calls that read and change the request or connection being
+    /// processed, such as its body, or that stop processing it
+    pub stream: Vec<Import>,
+    /// `proxy_*` imports of no ABI version, which no host provides
+    pub unknown: Vec<Import>,
+}
+
+/// The ABI versions a host call is part of
+#[derive(Clone, Copy)]
+enum Since {
+    All,
+    /// Removed in 0.2.0
+    Only0_1,
+    V0_2_0,
+    V0_2_1,
+}
+
+impl Since {
+    fn allows(self, abi: ProxyWasmAbi) -> bool {
+        match self {
+            Since::All => true,
+            Since::Only0_1 => abi == ProxyWasmAbi::V0_1_0,
+            Since::V0_2_0 => abi != ProxyWasmAbi::V0_1_0,
+            Since::V0_2_1 => abi == ProxyWasmAbi::V0_2_1,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Category {
+    Headers,
+    HttpCalls,
+    SharedData,
+    Metrics,
+    Timers,
+    Logging,
+    Properties,
+    Stream,
+}
+
+#[rustfmt::skip]
+const CALLS: &[(&str, Category, Since)] = &[
+    ("proxy_log", Category::Logging, Since::All),
+    ("proxy_get_log_level", Category::Logging, Since::V0_2_1),
+    ("proxy_get_current_time_nanoseconds", Category::Timers, Since::All),
+    ("proxy_set_tick_period_milliseconds", Category::Timers, Since::All),
+    ("proxy_get_property", Category::Properties, Since::All),
+    ("proxy_set_property", Category::Properties, Since::All),
+    ("proxy_get_header_map_pairs", Category::Headers, Since::All),
+    ("proxy_set_header_map_pairs", Category::Headers, Since::All),
+    ("proxy_get_header_map_value", Category::Headers, Since::All),
+    ("proxy_replace_header_map_value", Category::Headers, Since::All),
+    ("proxy_remove_header_map_value", Category::Headers, Since::All),
+    ("proxy_add_header_map_value", Category::Headers, Since::All),
+    ("proxy_http_call", Category::HttpCalls, Since::All),
+    ("proxy_grpc_call", Category::HttpCalls, Since::All),
+    ("proxy_grpc_stream", Category::HttpCalls, Since::All),
+    ("proxy_grpc_send", Category::HttpCalls, Since::All),
+    ("proxy_grpc_cancel", Category::HttpCalls, Since::All),
+    ("proxy_grpc_close", Category::HttpCalls, Since::All),
+    ("proxy_get_shared_data", Category::SharedData, Since::All),
+    ("proxy_set_shared_data", Category::SharedData, Since::All),
+    ("proxy_register_shared_queue", Category::SharedData, Since::All),
+    ("proxy_resolve_shared_queue", Category::SharedData, Since::All),
+    ("proxy_dequeue_shared_queue", Category::SharedData, Since::All),
+    ("proxy_enqueue_shared_queue", Category::SharedData, Since::All),
+    ("proxy_define_metric", Category::Metrics, Since::All),
+    ("proxy_increment_metric", Category::Metrics, Since::All),
+    ("proxy_record_metric", Category::Metrics, Since::All),
+    ("proxy_get_metric", Category::Metrics, Since::All),
+    ("proxy_get_buffer_bytes", Category::Stream, Since::All),
+    ("proxy_set_buffer_bytes", Category::Stream, Since::All),
+    ("proxy_send_local_response", Category::Stream, Since::All),
+    ("proxy_set_effective_context", Category::Stream, Since::All),
+    ("proxy_done", Category::Stream, Since::All),
+    ("proxy_get_status", Category::Stream, Since::All),
+    ("proxy_continue_request", Category::Stream, Since::Only0_1),
+    ("proxy_continue_response", Category::Stream, Since::Only0_1),
+    ("proxy_clear_route_cache", Category::Stream, Since::Only0_1),
+    ("proxy_continue_stream", Category::Stream, Since::V0_2_0),
+    ("proxy_close_stream", Category::Stream, Since::V0_2_0),
+    ("proxy_call_foreign_function", Category::Stream, Since::V0_2_0),
+];
+
+/// The number of `i32` parameters of every callback and whether it returns
+/// an `i32`. The ABI passes ids, sizes and flags as `i32`.
+#[rustfmt::skip]
+const CALLBACKS: &[(&str, usize, bool)] = &[
+    ("proxy_on_memory_allocate", 1, true),
+    ("proxy_on_vm_start", 2, true),
+    ("proxy_on_configure", 2, true),
+    ("proxy_on_context_create", 2, false),
+    ("proxy_on_log", 1, false),
+    ("proxy_on_done", 1, true),
+    ("proxy_on_delete", 1, false),
+    ("proxy_on_tick", 1, false),
+    ("proxy_on_queue_ready", 2, false),
+    ("proxy_on_new_connection", 1, true),
+    ("proxy_on_downstream_data", 3, true),
+    ("proxy_on_downstream_connection_close", 2, false),
+    ("proxy_on_upstream_data", 3, true),
+    ("proxy_on_upstream_connection_close", 2, false),
+    ("proxy_on_request_headers", 3, true),
+    ("proxy_on_request_body", 3, true),
+    ("proxy_on_request_trailers", 2, true),
+    ("proxy_on_request_metadata", 2, true),
+    ("proxy_on_response_headers", 3, true),
+    ("proxy_on_response_body", 3, true),
+    ("proxy_on_response_trailers", 2, true),
+    ("proxy_on_response_metadata", 2, true),
+    ("proxy_on_http_call_response", 5, false),
+    ("proxy_on_grpc_receive_initial_metadata", 3, false),
+    ("proxy_on_grpc_receive", 3, false),
+    ("proxy_on_grpc_receive_trailing_metadata", 3, false),
+    ("proxy_on_grpc_close", 3, false),
+    ("proxy_on_foreign_function", 3, true),
+];
+
+impl ProxyWasmAssumptions {
+    /// Recognize a proxy-wasm filter by its `proxy_*` imports and exports,
+    /// and check its callbacks against the ABI it declares
+    pub(crate) fn detect(module: &ParsedModule) -> Option<ProxyWasmAssumptions> {
+        let imports = module
+            .imports
+            .iter()
+            .any(|i| i.module == "env" && i.name.starts_with("proxy_"));
+        let exports = module
+            .exports
+            .iter()
+            .any(|e| e.name.starts_with("proxy_on_") || e.name.starts_with("proxy_abi_version_"));
+        if !imports && !exports {
+            return None;
+        }
+
+        let abi = module
+            .exports
+            .iter()
+            .find_map(|e| ProxyWasmAbi::from_export(e.name));
+        let mut missing_exports = Vec::new();
+        if module.export_signature("proxy_on_context_create").is_none() {
+            missing_exports.push("proxy_on_context_create".to_owned());
+        }
+        // The host allocates memory in the filter to pass data to it
+        if module
+            .export_signature("proxy_on_memory_allocate")
+            .is_none()
+            && module.export_signature("malloc").is_none()
+        {
+            missing_exports.push("proxy_on_memory_allocate".to_owned());
+        }
+
+        let mut export_mismatches = Vec::new();
+        for &(name, mut params, result) in CALLBACKS {
+            // 0.1.0 did not tell header callbacks about the end of stream
+            if abi == Some(ProxyWasmAbi::V0_1_0)
+                && (name == "proxy_on_request_headers" || name == "proxy_on_response_headers")
+            {
+                params = 2;
+            }
+            let expected = Signature {
+                params: vec![ValueType::I32; params],
+                results: if result {
+                    vec![ValueType::I32]
+                } else {
+                    Vec::new()
+                },
+            };
+            match module.export_signature(name) {
+                Some(signature) if *signature != expected => {
+                    export_mismatches.push(ExportMismatch {
+                        export: name.to_owned(),
+                        signature: signature.clone(),
+                        expected,
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        Some(ProxyWasmAssumptions {
+            abi,
+            missing_exports,
+            export_mismatches,
+            ..ProxyWasmAssumptions::default()
+        })
+    }
+}
+
+impl Classifier for ProxyWasmAssumptions {
+    fn classify(&mut self, import: Import) -> Result<(), Import> {
+        if import.module != "env" || !import.field.starts_with("proxy_") {
+            return Err(import);
+        }
+        let (category, since) = match CALLS.iter().find(|(name, _, _)| *name == import.field) {
+            Some(&(_, category, since)) => (category, since),
+            None => {
+                self.unknown.push(import);
+                return Ok(());
+            }
+        };
+        if let Some(abi) = self.abi {
+            if !since.allows(abi) {
+                self.disallowed.push(import.clone());
+            }
+        }
+        let calls = match category {
+            Category::Headers => &mut self.headers,
+            Category::HttpCalls => &mut self.http_calls,
+            Category::SharedData => &mut self.shared_data,
+            Category::Metrics => &mut self.metrics,
+            Category::Timers => &mut self.timers,
+            Category::Logging => &mut self.logging,
+            Category::Properties => &mut self.properties,
+            Category::Stream => &mut self.stream,
+        };
+        calls.push(import);
+        Ok(())
+    }
+}
+
+impl Profile for ProxyWasmAssumptions {
+    fn id(&self) -> &'static str {
+        "proxy_wasm"
+    }
+
+    fn name(&self) -> &'static str {
+        "proxy-wasm"
+    }
+
+    fn categories(&self) -> Vec<(&'static str, &[Import])> {
+        vec![
+            ("headers", &self.headers),
+            ("http_calls", &self.http_calls),
+            ("shared_data", &self.shared_data),
+            ("metrics", &self.metrics),
+            ("timers", &self.timers),
+            ("logging", &self.logging),
+            ("properties", &self.properties),
+            ("stream", &self.stream),
+        ]
+    }
+
+    fn unknown(&self) -> &[Import] {
+        &self.unknown
+    }
+
+    fn notes(&self) -> Vec<String> {
+        match self.abi {
+            Some(abi) => vec![format!("The filter targets ABI version {}", abi.version())],
+            None => Vec::new(),
+        }
+    }
+
+    fn errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.abi.is_none() {
+            errors.push("the filter exports no proxy_abi_version_* function".to_owned());
+        }
+        for export in &self.missing_exports {
+            errors.push(format!("the filter does not export {}", export));
+        }
+        errors.extend(self.export_mismatches.iter().map(|m| m.to_string()));
+        if let Some(abi) = self.abi {
+            for import in &self.disallowed {
+                errors.push(format!(
+                    "{} is not part of ABI version {}",
+                    import.field,
+                    abi.version()
+                ));
+            }
+        }
+        for import in &self.unknown {
+            errors.push(match self.abi {
+                Some(abi) => format!(
+                    "{} is not provided by ABI version {}",
+                    import.field,
+                    abi.version()
+                ),
+                None => format!("{} is not provided by any ABI version", import.field),
+            });
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, fields};
+
+    fn filter(wat: &str) -> ProxyWasmAssumptions {
+        analyze(wat).assumptions.proxy_wasm.unwrap()
+    }
+
+    #[test]
+    fn calls_belong_to_their_abi_versions() {
+        use ProxyWasmAbi::*;
+        let versions = |since: Since| {
+            [V0_1_0, V0_2_0, V0_2_1]
+                .iter()
+                .map(|&abi| since.allows(abi))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(versions(Since::All), vec![true, true, true]);
+        assert_eq!(versions(Since::Only0_1), vec![true, false, false]);
+        assert_eq!(versions(Since::V0_2_0), vec![false, true, true]);
+        assert_eq!(versions(Since::V0_2_1), vec![false, false, true]);
+        for (name, _, _) in CALLS {
+            assert_eq!(
+                CALLS.iter().filter(|(other, _, _)| other == name).count(),
+                1,
+                "{} is listed twice",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn flags_calls_outside_the_declared_abi() {
+        let filter = filter(
+            r#"(module
+                (import "env" "proxy_log" (func (param i32 i32 i32) (result i32)))
+                (import "env" "proxy_continue_request" (func (result i32)))
+                (import "env" "proxy_continue_stream" (func (param i32) (result i32)))
+                (import "env" "proxy_get_log_level" (func (param i32) (result i32)))
+                (import "env" "proxy_frobnicate" (func))
+                (func (export "proxy_abi_version_0_1_0"))
+                (func (export "proxy_on_context_create") (param i32 i32))
+                (func (export "proxy_on_memory_allocate") (param i32) (result i32)
+                    i32.const 0))"#,
+        );
+        assert_eq!(filter.abi, Some(ProxyWasmAbi::V0_1_0));
+        assert_eq!(
+            fields(&filter.disallowed),
+            vec!["proxy_continue_stream", "proxy_get_log_level"]
+        );
+        assert_eq!(
+            fields(&filter.logging),
+            vec!["proxy_log", "proxy_get_log_level"]
+        );
+        assert_eq!(
+            fields(&filter.stream),
+            vec!["proxy_continue_request", "proxy_continue_stream"]
+        );
+        assert_eq!(fields(&filter.unknown), vec!["proxy_frobnicate"]);
+        assert!(filter.missing_exports.is_empty());
+        assert_eq!(
+            filter.errors(),
+            vec![
+                "proxy_continue_stream is not part of ABI version 0.1.0",
+                "proxy_get_log_level is not part of ABI version 0.1.0",
+                "proxy_frobnicate is not provided by ABI version 0.1.0",
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_callbacks_against_the_declared_abi() {
+        let module = |abi: &str| {
+            format!(
+                r#"(module
+                    (func (export "proxy_abi_version_{}"))
+                    (func (export "malloc") (param i32) (result i32) i32.const 0)
+                    (func (export "proxy_on_request_headers") (param i32 i32) (result i32)
+                        i32.const 0))"#,
+                abi
+            )
+        };
+        let old = filter(&module("0_1_0"));
+        assert!(old.export_mismatches.is_empty());
+        assert_eq!(old.missing_exports, vec!["proxy_on_context_create"]);
+
+        let new = filter(&module("0_2_1"));
+        let mismatch = &new.export_mismatches[0];
+        assert_eq!(mismatch.export, "proxy_on_request_headers");
+        assert_eq!(mismatch.expected.params, vec![ValueType::I32; 3]);
+    }
+
+    #[test]
+    fn requires_an_abi_version() {
+        let filter = filter(r#"(module (import "env" "proxy_log" (func)))"#);
+        assert_eq!(filter.abi, None);
+        assert!(filter.disallowed.is_empty());
+        assert_eq!(
+            filter.errors()[0],
+            "the filter exports no proxy_abi_version_* function"
+        );
+    }
+
+    #[test]
+    fn plain_wasi_modules_are_not_filters() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "env" "proxy" (func))
+                (func (export "_start")))"#,
+        );
+        assert!(report.assumptions.proxy_wasm.is_none());
+        assert_eq!(fields(&report.assumptions.unknown), vec!["proxy"]);
+    }
+}
@@ -0,0 +1,16 @@
+use crate::{Assumptions, Nondeterminism, ProposalUse};
+
+/// Everything kontrolleur found out about a wasm binary
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Report {
+    /// The imports of the binary, grouped by what they give access to
+    pub assumptions: Assumptions,
+    /// The post-MVP proposals the binary relies on, with the first place each
+    /// of them is used, in the order they appear in the binary
+    pub proposals: Vec<ProposalUse>,
+    /// The sources of nondeterminism in the binary, if the analyzer looked
+    /// for them
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub determinism: Option<Vec<Nondeterminism>>,
+}
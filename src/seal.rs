@@ -0,0 +1,307 @@
+use crate::{module::ParsedModule, profile::Classifier, Import, Limits, Profile};
+use wasmparser::TypeRef;
+
+/// The memory pages the default schedule of pallet-contracts allows a
+/// contract, 1 MiB
+pub const MAX_MEMORY_PAGES: u64 = 16;
+
+/// The pallet-contracts host functions an ink! contract calls, grouped by
+/// what they give access to
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SealAssumptions {
+    /// The versions of the host functions the contract imports, like
+    /// `seal0`, in the order of first import
+    pub versions: Vec<String>,
+    /// The limits of `env.memory`, if the contract imports it
+    pub memory: Option<Limits>,
+    /// The exports the pallet calls but the contract lacks
+    pub missing_exports: Vec<String>,
+    pub storage: Vec<Import>,
+    /// Calling and instantiating other contracts, dispatching runtime calls
+    /// and XCM
+    pub calls: Vec<Import>,
+    /// Transferring value and reading balances, fees and gas
+    pub balance: Vec<Import>,
+    pub crypto: Vec<Import>,
+    pub chain_extensions: Vec<Import>,
+    /// The caller, the contract's own account and the block it runs in
+    pub context: Vec<Import>,
+    pub events: Vec<Import>,
+    /// The contract's input and return value
+    pub io: Vec<Import>,
+    pub debug: Vec<Import>,
+    /// Imports of the seal modules kontrolleur does not know about
+    pub unknown: Vec<Import>,
+}
+
+/// The modules pallet-contracts provides host functions in
+const MODULES: &[&str] = &["seal0", "seal1", "seal2", "__unstable__"];
+
+impl SealAssumptions {
+    /// Recognize an ink! contract by its imports of the seal modules
+    pub(crate) fn detect(module: &ParsedModule) -> Option<SealAssumptions> {
+        let mut versions: Vec<String> = Vec::new();
+        for import in &module.imports {
+            if MODULES.contains(&import.module) && !versions.iter().any(|v| v == import.module) {
+                versions.push(import.module.to_owned());
+            }
+        }
+        if versions.is_empty() {
+            return None;
+        }
+        let memory = module.imports.iter().find_map(|i| match i.ty {
+            TypeRef::Memory(ty) if i.module == "env" && i.name == "memory" => Some(Limits {
+                initial: ty.initial,
+                maximum: ty.maximum,
+            }),
+            _ => None,
+        });
+        let missing_exports = ["deploy", "call"]
+            .iter()
+            .filter(|name| module.export_signature(name).is_none())
+            .map(|name| name.to_string())
+            .collect();
+        Some(SealAssumptions {
+            versions,
+            memory,
+            missing_exports,
+            ..SealAssumptions::default()
+        })
+    }
+}
+
+impl Classifier for SealAssumptions {
+    fn classify(&mut self, import: Import) -> Result<(), Import> {
+        if !MODULES.contains(&import.module.as_str()) {
+            return Err(import);
+        }
+        // Older releases of the pallet prefix every function with `seal_`
+        let name = import.field.strip_prefix("seal_").unwrap_or(&import.field);
+        let calls = match name {
+            "set_storage"
+            | "clear_storage"
+            | "get_storage"
+            | "contains_storage"
+            | "take_storage"
+            | "set_transient_storage"
+            | "clear_transient_storage"
+            | "get_transient_storage"
+            | "contains_transient_storage"
+            | "take_transient_storage" => &mut self.storage,
+            "call"
+            | "delegate_call"
+            | "instantiate"
+            | "terminate"
+            | "call_runtime"
+            | "xcm_execute"
+            | "xcm_send"
+            | "set_code_hash"
+            | "code_hash"
+            | "own_code_hash"
+            | "is_contract"
+            | "lock_delegate_dependency"
+            | "unlock_delegate_dependency"
+            | "add_delegate_dependency"
+            | "remove_delegate_dependency" => &mut self.calls,
+            "transfer"
+            | "balance"
+            | "value_transferred"
+            | "minimum_balance"
+            | "gas_left"
+            | "weight_to_fee"
+            | "tombstone_deposit"
+            | "rent_allowance"
+            | "set_rent_allowance"
+            | "instantiation_nonce" => &mut self.balance,
+            "hash_sha2_256"
+            | "hash_keccak_256"
+            | "hash_blake2_256"
+            | "hash_blake2_128"
+            | "ecdsa_recover"
+            | "ecdsa_to_eth_address"
+            | "sr25519_verify"
+            | "random" => &mut self.crypto,
+            "call_chain_extension" => &mut self.chain_extensions,
+            "caller" | "caller_is_origin" | "caller_is_root" | "address" | "now"
+            | "block_number" | "account_id" => &mut self.context,
+            "deposit_event" => &mut self.events,
+            "input" | "return" => &mut self.io,
+            "debug_message" => &mut self.debug,
+            _ => &mut self.unknown,
+        };
+        calls.push(import);
+        Ok(())
+    }
+}
+
+impl Profile for SealAssumptions {
+    fn id(&self) -> &'static str {
+        "seal"
+    }
+
+    fn name(&self) -> &'static str {
+        "pallet-contracts"
+    }
+
+    fn categories(&self) -> Vec<(&'static str, &[Import])> {
+        vec![
+            ("storage", &self.storage),
+            ("calls", &self.calls),
+            ("balance", &self.balance),
+            ("crypto", &self.crypto),
+            ("chain_extensions", &self.chain_extensions),
+            ("context", &self.context),
+            ("events", &self.events),
+            ("io", &self.io),
+            ("debug", &self.debug),
+        ]
+    }
+
+    fn unknown(&self) -> &[Import] {
+        &self.unknown
+    }
+
+    fn notes(&self) -> Vec<String> {
+        let mut notes = Vec::new();
+        if self.versions.len() > 1 {
+            notes.push(format!(
+                "The contract mixes the host function versions {}",
+                self.versions.join(", ")
+            ));
+        } else {
+            notes.push(format!(
+                "The contract uses the host function version {}",
+                self.versions.join(", ")
+            ));
+        }
+        if self.versions.iter().any(|v| v == "__unstable__") {
+            notes.push(
+                "The contract uses unstable host functions, which only development chains provide"
+                    .to_owned(),
+            );
+        }
+        notes
+    }
+
+    fn errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        match self.memory {
+            None => errors.push("the contract does not import env.memory".to_owned()),
+            Some(Limits { maximum: None, .. }) => {
+                errors.push("env.memory has no maximum size".to_owned())
+            }
+            Some(Limits {
+                initial,
+                maximum: Some(maximum),
+            }) => {
+                if maximum > MAX_MEMORY_PAGES {
+                    errors.push(format!(
+                        "env.memory may grow to {} pages but the pallet allows at most {}",
+                        maximum, MAX_MEMORY_PAGES
+                    ));
+                }
+                if initial > maximum {
+                    errors.push(format!(
+                        "env.memory starts with {} pages, more than its maximum of {}",
+                        initial, maximum
+                    ));
+                }
+            }
+        }
+        for export in &self.missing_exports {
+            errors.push(format!("the contract does not export {}", export));
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, fields};
+
+    #[test]
+    fn sorts_host_functions() {
+        let report = analyze(
+            r#"(module
+                (import "seal0" "seal_get_storage" (func (param i32 i32 i32) (result i32)))
+                (import "seal1" "call" (func (param i32 i32 i64 i32 i32 i32 i32 i32) (result i32)))
+                (import "seal0" "value_transferred" (func (param i32 i32)))
+                (import "seal0" "hash_blake2_256" (func (param i32 i32 i32)))
+                (import "seal0" "call_chain_extension" (func (param i32 i32 i32 i32 i32) (result i32)))
+                (import "seal0" "caller" (func (param i32 i32)))
+                (import "seal0" "deposit_event" (func (param i32 i32 i32 i32)))
+                (import "seal0" "input" (func (param i32 i32)))
+                (import "seal0" "debug_message" (func (param i32 i32) (result i32)))
+                (import "seal0" "teleport" (func))
+                (import "env" "memory" (memory 2 16))
+                (func (export "deploy"))
+                (func (export "call")))"#,
+        );
+        let seal = report.assumptions.seal.as_ref().unwrap();
+        assert_eq!(seal.versions, vec!["seal0", "seal1"]);
+        assert_eq!(fields(&seal.storage), vec!["seal_get_storage"]);
+        assert_eq!(fields(&seal.calls), vec!["call"]);
+        assert_eq!(fields(&seal.balance), vec!["value_transferred"]);
+        assert_eq!(fields(&seal.crypto), vec!["hash_blake2_256"]);
+        assert_eq!(fields(&seal.chain_extensions), vec!["call_chain_extension"]);
+        assert_eq!(fields(&seal.context), vec!["caller"]);
+        assert_eq!(fields(&seal.events), vec!["deposit_event"]);
+        assert_eq!(fields(&seal.io), vec!["input"]);
+        assert_eq!(fields(&seal.debug), vec!["debug_message"]);
+        assert_eq!(fields(seal.unknown()), vec!["teleport"]);
+        assert_eq!(
+            seal.notes(),
+            vec!["The contract mixes the host function versions seal0, seal1"]
+        );
+        assert!(seal.errors().is_empty());
+    }
+
+    #[test]
+    fn checks_memory_and_exports() {
+        let errors = |wat: &str| analyze(wat).assumptions.seal.unwrap().errors();
+        assert_eq!(
+            errors(r#"(module (import "seal0" "input" (func (param i32 i32))))"#),
+            vec![
+                "the contract does not import env.memory",
+                "the contract does not export deploy",
+                "the contract does not export call",
+            ]
+        );
+        assert_eq!(
+            errors(
+                r#"(module
+                    (import "seal0" "input" (func (param i32 i32)))
+                    (import "env" "memory" (memory 1))
+                    (func (export "deploy"))
+                    (func (export "call")))"#
+            ),
+            vec!["env.memory has no maximum size"]
+        );
+        assert_eq!(
+            errors(
+                r#"(module
+                    (import "__unstable__" "take_storage" (func))
+                    (import "env" "memory" (memory 1 32))
+                    (func (export "deploy"))
+                    (func (export "call")))"#
+            ),
+            vec!["env.memory may grow to 32 pages but the pallet allows at most 16"]
+        );
+    }
+
+    #[test]
+    fn plain_wasi_modules_are_not_contracts() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "env" "memory" (memory 1 16))
+                (import "env" "seal_input" (func))
+                (func (export "call")))"#,
+        );
+        assert!(report.assumptions.seal.is_none());
+        assert_eq!(fields(&report.assumptions.unknown), vec!["seal_input"]);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{
+    module::ParsedModule, profile::Classifier, ExportMismatch, Import, Profile, Signature,
+    ValueType,
+};
+
+/// The `vm_hooks` an Arbitrum Stylus program calls, grouped by what they
+/// give access to
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StylusAssumptions {
+    /// Whether the program exports `user_entrypoint`, the function Stylus
+    /// calls with the length of the call data
+    pub entrypoint: bool,
+    pub entrypoint_mismatch: Option<ExportMismatch>,
+    pub storage: Vec<Import>,
+    /// Calling and creating other contracts and reading what they return
+    pub external_calls: Vec<Import>,
+    pub logs: Vec<Import>,
+    /// Reading the block, transaction, message and accounts the program
+    /// runs with
+    pub context: Vec<Import>,
+    pub crypto: Vec<Import>,
+    /// The 256 bit arithmetic of the EVM
+    pub math: Vec<Import>,
+    /// Reading the call data, writing the result and paying for memory
+    pub io: Vec<Import>,
+    /// Imports of `vm_hooks` kontrolleur does not know about
+    pub unknown: Vec<Import>,
+}
+
+impl StylusAssumptions {
+    /// Recognize a Stylus program by its `vm_hooks` imports or its entrypoint
+    pub(crate) fn detect(module: &ParsedModule) -> Option<StylusAssumptions> {
+        let hooks = module.imports.iter().any(|i| i.module == "vm_hooks");
+        let signature = module.export_signature("user_entrypoint");
+        if !hooks && signature.is_none() {
+            return None;
+        }
+        let expected = Signature {
+            params: vec![ValueType::I32],
+            results: vec![ValueType::I32],
+        };
+        let entrypoint_mismatch = match signature {
+            Some(signature) if *signature != expected => Some(ExportMismatch {
+                export: "user_entrypoint".to_owned(),
+                signature: signature.clone(),
+                expected,
+            }),
+            _ => None,
+        };
+        Some(StylusAssumptions {
+            entrypoint: signature.is_some(),
+            entrypoint_mismatch,
+            ..StylusAssumptions::default()
+        })
+    }
+}
+
+impl Classifier for StylusAssumptions {
+    fn classify(&mut self, import: Import) -> Result<(), Import> {
+        if import.module != "vm_hooks" {
+            return Err(import);
+        }
+        let calls = match import.field.as_str() {
+            field if field.starts_with("storage_") => &mut self.storage,
+            "call_contract"
+            | "delegate_call_contract"
+            | "static_call_contract"
+            | "create1"
+            | "create2"
+            | "read_return_data"
+            | "return_data_size" => &mut self.external_calls,
+            "emit_log" => &mut self.logs,
+            field
+                if field.starts_with("account_")
+                    || field.starts_with("block_")
+                    || field.starts_with("msg_")
+                    || field.starts_with("tx_") =>
+            {
+                &mut self.context
+            }
+            "chainid" | "contract_address" | "evm_gas_left" | "evm_ink_left" => &mut self.context,
+            "native_keccak256" => &mut self.crypto,
+            field if field.starts_with("math_") => &mut self.math,
+            "read_args" | "write_result" | "pay_for_memory_grow" => &mut self.io,
+            _ => &mut self.unknown,
+        };
+        calls.push(import);
+        Ok(())
+    }
+}
+
+impl Profile for StylusAssumptions {
+    fn id(&self) -> &'static str {
+        "stylus"
+    }
+
+    fn name(&self) -> &'static str {
+        "Arbitrum Stylus"
+    }
+
+    fn categories(&self) -> Vec<(&'static str, &[Import])> {
+        vec![
+            ("storage", &self.storage),
+            ("external_calls", &self.external_calls),
+            ("logs", &self.logs),
+            ("context", &self.context),
+            ("crypto", &self.crypto),
+            ("math", &self.math),
+            ("io", &self.io),
+        ]
+    }
+
+    fn unknown(&self) -> &[Import] {
+        &self.unknown
+    }
+
+    fn errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !self.entrypoint {
+            errors.push("the program does not export user_entrypoint".to_owned());
+        }
+        errors.extend(self.entrypoint_mismatch.iter().map(|m| m.to_string()));
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, fields};
+
+    #[test]
+    fn sorts_vm_hooks() {
+        let report = analyze(
+            r#"(module
+                (import "vm_hooks" "storage_load_bytes32" (func (param i32 i32)))
+                (import "vm_hooks" "call_contract" (func (param i32 i32 i32 i32 i64 i32) (result i32)))
+                (import "vm_hooks" "emit_log" (func (param i32 i32 i32)))
+                (import "vm_hooks" "msg_sender" (func (param i32)))
+                (import "vm_hooks" "chainid" (func (result i64)))
+                (import "vm_hooks" "native_keccak256" (func (param i32 i32 i32)))
+                (import "vm_hooks" "math_div" (func (param i32 i32)))
+                (import "vm_hooks" "read_args" (func (param i32)))
+                (import "vm_hooks" "teleport" (func))
+                (func (export "user_entrypoint") (param i32) (result i32) i32.const 0))"#,
+        );
+        let stylus = report.assumptions.stylus.as_ref().unwrap();
+        assert_eq!(fields(&stylus.storage), vec!["storage_load_bytes32"]);
+        assert_eq!(fields(&stylus.external_calls), vec!["call_contract"]);
+        assert_eq!(fields(&stylus.logs), vec!["emit_log"]);
+        assert_eq!(fields(&stylus.context), vec!["msg_sender", "chainid"]);
+        assert_eq!(fields(&stylus.crypto), vec!["native_keccak256"]);
+        assert_eq!(fields(&stylus.math), vec!["math_div"]);
+        assert_eq!(fields(&stylus.io), vec!["read_args"]);
+        assert_eq!(fields(stylus.unknown()), vec!["teleport"]);
+        assert!(stylus.entrypoint);
+        assert!(stylus.errors().is_empty());
+    }
+
+    #[test]
+    fn checks_the_entrypoint() {
+        let errors = |wat: &str| analyze(wat).assumptions.stylus.unwrap().errors();
+        assert_eq!(
+            errors(r#"(module (import "vm_hooks" "read_args" (func (param i32))))"#),
+            vec!["the program does not export user_entrypoint"]
+        );
+        assert_eq!(
+            errors(r#"(module (func (export "user_entrypoint") (param i64)))"#),
+            vec!["user_entrypoint is exported as (i64) -> () but is expected to be (i32) -> i32"]
+        );
+    }
+
+    #[test]
+    fn plain_wasi_modules_are_not_programs() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "env" "read_args" (func (param i32)))
+                (func (export "_start")))"#,
+        );
+        assert!(report.assumptions.stylus.is_none());
+        assert_eq!(fields(&report.assumptions.unknown), vec!["read_args"]);
+    }
+}
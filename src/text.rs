@@ -0,0 +1,649 @@
+use crate::{
+    ChangedImport, ComponentItem, CoreModule, Diff, Explanation, Import, ImportSource, Limits,
+    Reachability, Report, WasiSnapshot,
+};
+use std::io::{self, Write};
+
+impl Report {
+    /// Write the report as English prose meant to be read by humans
+    pub fn write_text<W: Write>(&self, mut w: W, verbose: bool) -> io::Result<()> {
+        let assumptions = &self.assumptions;
+        let total_count = assumptions.count();
+        writeln!(
+            w,
+            "There {} {} total external API call{}.",
+            correct_to_be_form(total_count),
+            total_count,
+            optional_s(total_count)
+        )?;
+
+        if let Some(component) = &assumptions.component {
+            writeln!(w, "This binary is a component.")?;
+            if component.modules > 0 || component.components > 0 {
+                writeln!(
+                    w,
+                    "\tIt embeds {} core module{} and {} component{}",
+                    component.modules,
+                    optional_s(component.modules),
+                    component.components,
+                    optional_s(component.components)
+                )?;
+            }
+            for (title, items) in [
+                ("It imports:", &component.imports),
+                ("Its world exports:", &component.exports),
+            ] {
+                if items.is_empty() {
+                    continue;
+                }
+                writeln!(w, "\t{}", title)?;
+                for item in items {
+                    writeln!(w, "\t\t{}", component_item(item))?;
+                    if verbose {
+                        for function in &item.functions {
+                            writeln!(w, "\t\t\t{}", function)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        let wasi = &assumptions.wasi;
+        let wasi_count = wasi.count();
+        if wasi_count > 0 {
+            writeln!(w, "This binary is expecting a WASI compliant runtime.")?;
+            let snapshots: Vec<_> = wasi.snapshots.iter().map(|s| s.module_name()).collect();
+            writeln!(
+                w,
+                "\tThe binary targets the WASI snapshot{} {}",
+                optional_s(snapshots.len()),
+                snapshots.join(", ")
+            )?;
+            // The interfaces of WASI 0.2 are typed by the component model, so
+            // only the older snapshots can disagree
+            let layouts = wasi
+                .snapshots
+                .iter()
+                .filter(|s| **s != WasiSnapshot::Preview2)
+                .count();
+            if layouts > 1 {
+                writeln!(
+                    w,
+                    "\tMixing snapshots means calls may disagree on how data is laid out"
+                )?;
+            }
+            if !wasi.abi_differences.is_empty() {
+                writeln!(
+                w,
+                "\tThe following wasi_unstable calls differ in ABI from wasi_snapshot_preview1:"
+            )?;
+                for difference in &wasi.abi_differences {
+                    writeln!(w, "\t\t{}", difference.description())?;
+                }
+            }
+            if !wasi.signature_mismatches.is_empty() {
+                writeln!(
+                    w,
+                    "\tThe following imports do not match their definition in the snapshot:"
+                )?;
+                for mismatch in &wasi.signature_mismatches {
+                    writeln!(w, "\t\terror: {}", mismatch)?;
+                }
+            }
+            writeln!(
+                w,
+                "\tThe binary uses {} WASI call{}",
+                wasi_count,
+                optional_s(wasi_count)
+            )?;
+            writeln!(w, "\tThe following system resource types are used:")?;
+            let mut types = Vec::new();
+            if !wasi.file_system.is_empty() {
+                types.push("file system");
+            }
+            if !wasi.environment.is_empty() {
+                types.push("environment");
+            }
+            if !wasi.process.is_empty() {
+                types.push("process");
+            }
+            if !wasi.network.is_empty() {
+                types.push("network");
+            }
+            writeln!(w, "\t\t{}", types.join(", "))?;
+            if verbose {
+                if !wasi.file_system.is_empty() {
+                    writeln!(w, "\tFile system calls:")?;
+                    for call in &wasi.file_system {
+                        writeln!(w, "\t\t{}", wasi_call(call))?;
+                    }
+                }
+                if !wasi.environment.is_empty() {
+                    writeln!(w, "\tEnivronent system calls:")?;
+                    for call in &wasi.environment {
+                        writeln!(w, "\t\t{}", wasi_call(call))?;
+                    }
+                }
+                if !wasi.process.is_empty() {
+                    writeln!(w, "\tProcess system calls:")?;
+                    for call in &wasi.process {
+                        writeln!(w, "\t\t{}", wasi_call(call))?;
+                    }
+                }
+                if !wasi.network.is_empty() {
+                    writeln!(w, "\tNetwork system calls:")?;
+                    for call in &wasi.network {
+                        writeln!(w, "\t\t{}", wasi_call(call))?;
+                    }
+                }
+            }
+            let unknown = &wasi.unknown;
+            let unknown_count = unknown.len();
+            if unknown_count > 0 {
+                writeln!(
+                    w,
+                    "There {} {} unknown wasi sys call{}:",
+                    correct_to_be_form(unknown_count),
+                    unknown_count,
+                    optional_s(unknown_count)
+                )?;
+                for call in unknown {
+                    writeln!(w, "\t{}", wasi_call(call))?;
+                }
+            }
+        }
+
+        for profile in assumptions.profiles() {
+            writeln!(
+                w,
+                "This binary follows the conventions of {}.",
+                profile.name()
+            )?;
+            for note in profile.notes() {
+                writeln!(w, "\t{}", note)?;
+            }
+            let errors = profile.errors();
+            if !errors.is_empty() {
+                writeln!(
+                    w,
+                    "\tThe binary breaks the following rules of {}:",
+                    profile.name()
+                )?;
+                for error in errors {
+                    writeln!(w, "\t\terror: {}", error)?;
+                }
+            }
+            let count = profile.count();
+            let categories: Vec<_> = profile
+                .categories()
+                .into_iter()
+                .filter(|(_, imports)| !imports.is_empty())
+                .collect();
+            writeln!(
+                w,
+                "\tThe binary uses {} {} call{}",
+                count,
+                profile.name(),
+                optional_s(count)
+            )?;
+            if !categories.is_empty() {
+                let names: Vec<_> = categories
+                    .iter()
+                    .map(|(category, _)| describe(category))
+                    .collect();
+                writeln!(w, "\tThe following system resource types are used:")?;
+                writeln!(w, "\t\t{}", names.join(", "))?;
+            }
+            if verbose {
+                for (category, imports) in categories {
+                    writeln!(w, "\t{} calls:", capitalize(&describe(category)))?;
+                    for call in imports {
+                        writeln!(w, "\t\t{}::{}", call.module, call.field)?;
+                    }
+                }
+            }
+            let entry_points = profile.entry_points();
+            if !entry_points.is_empty() {
+                writeln!(w, "\tThe exported methods use:")?;
+                for entry_point in entry_points {
+                    let names: Vec<_> = entry_point
+                        .categories
+                        .iter()
+                        .map(|category| describe(category))
+                        .collect();
+                    let mut names = if names.is_empty() {
+                        format!("no {} calls", profile.name())
+                    } else {
+                        names.join(", ")
+                    };
+                    if !entry_point.indirect_categories.is_empty() {
+                        let indirect: Vec<_> = entry_point
+                            .indirect_categories
+                            .iter()
+                            .map(|category| describe(category))
+                            .collect();
+                        names.push_str(&format!(
+                            ", and only through call_indirect: {}",
+                            indirect.join(", ")
+                        ));
+                    }
+                    writeln!(w, "\t\t{}: {}", entry_point.export, names)?;
+                }
+            }
+            let unknown = profile.unknown();
+            if !unknown.is_empty() {
+                writeln!(
+                    w,
+                    "There {} {} unknown {} call{}:",
+                    correct_to_be_form(unknown.len()),
+                    unknown.len(),
+                    profile.name(),
+                    optional_s(unknown.len())
+                )?;
+                for call in unknown {
+                    writeln!(w, "\t{}::{}", call.module, call.field)?;
+                }
+            }
+        }
+
+        if !assumptions.memories.is_empty() {
+            writeln!(w, "Imported memories:")?;
+            for memory in &assumptions.memories {
+                let import = &memory.import;
+                write!(
+                    w,
+                    "\t{}::{}: {}",
+                    import.module,
+                    import.field,
+                    limits(&memory.limits, "page")
+                )?;
+                if memory.shared {
+                    write!(w, ", shared")?;
+                }
+                if memory.memory64 {
+                    write!(w, ", 64 bit addresses")?;
+                }
+                writeln!(w)?;
+            }
+        }
+        if !assumptions.tables.is_empty() {
+            writeln!(w, "Imported tables:")?;
+            for table in &assumptions.tables {
+                let import = &table.import;
+                writeln!(
+                    w,
+                    "\t{}::{}: {}, {}",
+                    import.module,
+                    import.field,
+                    table.element_type,
+                    limits(&table.limits, "element")
+                )?;
+            }
+        }
+
+        if !assumptions.unknown.is_empty() {
+            writeln!(w, "Unknown imports:")?;
+            for unknown in &assumptions.unknown {
+                writeln!(w, "\t{}", unknown.field)?;
+            }
+        }
+
+        if self.proposals.is_empty() {
+            writeln!(
+                w,
+                "The binary only uses features of the first WebAssembly release."
+            )?;
+        } else {
+            writeln!(
+                w,
+                "The binary relies on the following WebAssembly proposals:"
+            )?;
+            for proposal in &self.proposals {
+                let place = match (&proposal.function_name, proposal.function) {
+                    (Some(name), _) => format!("function {}", name),
+                    (None, Some(index)) => format!("function {}", index),
+                    (None, None) => format!("the {}", proposal.section),
+                };
+                writeln!(
+                    w,
+                    "\t{}, first used in {} at byte offset {:#x}",
+                    proposal.proposal, place, proposal.offset
+                )?;
+            }
+        }
+
+        if let Some(determinism) = &self.determinism {
+            if determinism.is_empty() {
+                writeln!(w, "The binary has no known sources of nondeterminism.")?;
+            } else {
+                writeln!(w, "The binary can behave nondeterministically through:")?;
+            }
+            for finding in determinism {
+                let uses = finding.uses.join(", ");
+                let mut place = match (&finding.function_name, finding.function) {
+                    (Some(name), _) => format!(" in function {}", name),
+                    (None, Some(index)) => format!(" in function {}", index),
+                    (None, None) => String::new(),
+                };
+                if let Some(index) = finding.core_module {
+                    let preposition = if place.is_empty() { "in" } else { "of" };
+                    place.push_str(&format!(" {} core module {}", preposition, index));
+                }
+                writeln!(w, "\t{}{}: {}", finding.source, place, uses)?;
+            }
+        }
+
+        let imports = assumptions.categorized();
+        let walked: Vec<_> = imports
+            .iter()
+            .filter_map(|(category, import)| import.reachability.map(|r| (category, import, r)))
+            .collect();
+        if !walked.is_empty() {
+            writeln!(
+                w,
+                "Walking the calls from the exports and the start function:"
+            )?;
+            let mut exercised = Vec::new();
+            let mut declared = Vec::new();
+            for &(category, _, reachability) in &walked {
+                let name = describe(category);
+                if reachability.is_exercised() {
+                    declared.retain(|c| *c != name);
+                    if !exercised.contains(&name) {
+                        exercised.push(name);
+                    }
+                } else if !exercised.contains(&name) && !declared.contains(&name) {
+                    declared.push(name);
+                }
+            }
+            if !exercised.is_empty() {
+                writeln!(w, "\tCapabilities exercised: {}", exercised.join(", "))?;
+            }
+            if !declared.is_empty() {
+                writeln!(
+                    w,
+                    "\tCapabilities declared but never called: {}",
+                    declared.join(", ")
+                )?;
+            }
+            for (reachability, title) in &[
+                (
+                    Reachability::IndirectOnly,
+                    "Imports only called through call_indirect:",
+                ),
+                (Reachability::Unreachable, "Imports no code path calls:"),
+            ] {
+                let matching: Vec<_> = walked
+                    .iter()
+                    .filter(|(_, _, r)| r == reachability)
+                    .collect();
+                if !matching.is_empty() {
+                    writeln!(w, "\t{}", title)?;
+                    for (_, import, _) in matching {
+                        writeln!(w, "\t\t{}::{}", import.module, import.field)?;
+                    }
+                }
+            }
+        }
+
+        let modules = assumptions
+            .component
+            .as_ref()
+            .map_or(&[][..], |c| &c.core_modules);
+        for module in modules {
+            let adapter = if module.adapter {
+                ", the WASI adapter,"
+            } else {
+                ""
+            };
+            writeln!(
+                w,
+                "Core module {}{} at byte offset {:#x}:",
+                core_module(module),
+                adapter,
+                module.offset
+            )?;
+            let mut report = Vec::new();
+            module.report.write_text(&mut report, verbose)?;
+            for line in String::from_utf8_lossy(&report).lines() {
+                writeln!(w, "\t{}", line)?;
+            }
+            if module.links.is_empty() {
+                if module.report.assumptions.count() > 0 {
+                    writeln!(w, "\tThe component never instantiates the module")?;
+                }
+                continue;
+            }
+            writeln!(w, "\tIts imports are satisfied by:")?;
+            for link in &module.links {
+                writeln!(
+                    w,
+                    "\t\t{}::{}: {}",
+                    link.module,
+                    link.field,
+                    import_source(&link.source, modules)
+                )?;
+                if !link.reaches.is_empty() {
+                    writeln!(w, "\t\t\twhich can call {}", link.reaches.join(", "))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Diff {
+    /// Write the differences as English prose meant to be read by humans
+    pub fn write_text<W: Write>(&self, mut w: W) -> io::Result<()> {
+        if self.is_empty() {
+            writeln!(w, "Both binaries make the same assumptions.")?;
+            return Ok(());
+        }
+        write_imports(&mut w, "Added imports:", '+', &self.added_imports)?;
+        write_imports(&mut w, "Removed imports:", '-', &self.removed_imports)?;
+        write_list(&mut w, "Added categories:", '+', &self.added_categories)?;
+        write_list(&mut w, "Removed categories:", '-', &self.removed_categories)?;
+        let snapshots =
+            |s: &[crate::WasiSnapshot]| -> Vec<_> { s.iter().map(|s| s.module_name()).collect() };
+        write_list(
+            &mut w,
+            "Added WASI snapshots:",
+            '+',
+            &snapshots(&self.added_snapshots),
+        )?;
+        write_list(
+            &mut w,
+            "Removed WASI snapshots:",
+            '-',
+            &snapshots(&self.removed_snapshots),
+        )
+    }
+}
+
+impl Explanation {
+    /// Write the call chains as English prose meant to be read by humans
+    pub fn write_text<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let import = &self.import;
+        if let Some(index) = self.core_module {
+            writeln!(w, "In core module {} of the component:", index)?;
+        }
+        if self.chains.is_empty() {
+            return match import.reachability {
+                Some(Reachability::IndirectOnly) => writeln!(
+                    w,
+                    "{}::{} is only called through call_indirect, no chain of direct calls leads to it.",
+                    import.module, import.field
+                ),
+                _ => writeln!(
+                    w,
+                    "No code path calls {}::{}.",
+                    import.module, import.field
+                ),
+            };
+        }
+        writeln!(w, "{}::{} is called from:", import.module, import.field)?;
+        for chain in &self.chains {
+            writeln!(w, "\t{}: {}", chain.entry, chain.functions.join(" -> "))?;
+        }
+        Ok(())
+    }
+}
+
+fn write_imports<W: Write>(
+    w: &mut W,
+    title: &str,
+    sign: char,
+    imports: &[ChangedImport],
+) -> io::Result<()> {
+    let lines: Vec<_> = imports
+        .iter()
+        .map(|c| format!("{}::{} ({})", c.import.module, c.import.field, c.category))
+        .collect();
+    write_list(w, title, sign, &lines)
+}
+
+fn write_list<W: Write, T: AsRef<str>>(
+    w: &mut W,
+    title: &str,
+    sign: char,
+    items: &[T],
+) -> io::Result<()> {
+    if items.is_empty() {
+        return Ok(());
+    }
+    writeln!(w, "{}", title)?;
+    for item in items {
+        writeln!(w, "\t{} {}", sign, item.as_ref())?;
+    }
+    Ok(())
+}
+
+/// Describe the limits of a memory or table, such as `1 page initially, at
+/// most 16 pages`
+fn limits(limits: &Limits, unit: &str) -> String {
+    let initial = format!(
+        "{} {}{} initially",
+        limits.initial,
+        unit,
+        optional_s(limits.initial as usize)
+    );
+    match limits.maximum {
+        Some(maximum) => format!(
+            "{}, at most {} {}{}",
+            initial,
+            maximum,
+            unit,
+            optional_s(maximum as usize)
+        ),
+        None => format!("{}, no maximum", initial),
+    }
+}
+
+/// Name a WASI call by its field, or for WASI 0.2 along with its interface,
+/// like `wasi:cli/environment@0.2.0::get-arguments`
+fn wasi_call(call: &Import) -> String {
+    match WasiSnapshot::from_module_name(&call.module) {
+        Some(WasiSnapshot::Preview2) => format!("{}::{}", call.module, call.field),
+        _ => call.field.clone(),
+    }
+}
+
+/// Describe an import or export of a component, like `instance
+/// wasi:filesystem/types@0.2.0 (file system)`
+fn component_item(item: &ComponentItem) -> String {
+    match &item.category {
+        Some(category) => format!(
+            "{} {} ({})",
+            item.kind.name(),
+            item.name,
+            describe(category)
+        ),
+        None => format!("{} {}", item.kind.name(), item.name),
+    }
+}
+
+/// Name a core module of a component by its index and, if it has one, its
+/// name, like `1 (wit-component:adapter:wasi_snapshot_preview1)`
+fn core_module(module: &CoreModule) -> String {
+    match &module.name {
+        Some(name) => format!("{} ({})", module.index, name),
+        None => module.index.to_string(),
+    }
+}
+
+/// Describe what satisfies an import of a core module
+fn import_source(source: &ImportSource, modules: &[CoreModule]) -> String {
+    let module = |index: u32| match modules.get(index as usize) {
+        Some(module) => core_module(module),
+        None => index.to_string(),
+    };
+    match source {
+        ImportSource::Module {
+            module: index,
+            export,
+        } => {
+            format!("export {} of module {}", export, module(*index))
+        }
+        ImportSource::Adapter {
+            module: index,
+            export,
+        } => {
+            format!(
+                "export {} of the adapter, module {}",
+                export,
+                module(*index)
+            )
+        }
+        ImportSource::ComponentImport {
+            import,
+            function: Some(function),
+        } => format!("{} of the component import {}", function, import),
+        ImportSource::ComponentImport {
+            import,
+            function: None,
+        } => format!("the component import {}", import),
+        ImportSource::Builtin { name } => format!("the component model built-in {}", name),
+        ImportSource::Unresolved => "an item kontrolleur cannot trace".to_owned(),
+    }
+}
+
+fn correct_to_be_form(count: usize) -> &'static str {
+    if count == 1 {
+        "is"
+    } else {
+        "are"
+    }
+}
+fn optional_s(count: usize) -> &'static str {
+    if count == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+/// Turn a category id like `js_interop` into words like `JS interop`. The
+/// profile a category is prefixed with is left out.
+fn describe(category: &str) -> String {
+    let category = category.rsplit('.').next().unwrap_or(category);
+    category
+        .split('_')
+        .map(|word| match word {
+            "js" => "JS",
+            "dom" => "DOM",
+            "io" => "I/O",
+            "http" => "HTTP",
+            "kv" => "KV",
+            word => word,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,478 @@
+use crate::{Import, ImportKind, Signature, ValueType};
+use std::fmt;
+
+/// The WASI snapshots a binary can import from. Each snapshot before
+/// Preview 2 is its own import module and the ABI is not identical between
+/// them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WasiSnapshot {
+    /// `wasi_unstable`, also known as snapshot 0
+    Unstable,
+    /// `wasi_snapshot_preview1`
+    Preview1,
+    /// WASI 0.2, whose interfaces are imported by name, like
+    /// `wasi:filesystem/types@0.2.0`, by components and the core modules
+    /// in them
+    Preview2,
+}
+
+impl WasiSnapshot {
+    pub fn from_module_name(name: &str) -> Option<WasiSnapshot> {
+        match name {
+            "wasi_unstable" => Some(WasiSnapshot::Unstable),
+            "wasi_snapshot_preview1" => Some(WasiSnapshot::Preview1),
+            name if name.starts_with("wasi:") => Some(WasiSnapshot::Preview2),
+            _ => None,
+        }
+    }
+
+    /// The module the snapshot is imported from. Preview 2 has no module of
+    /// its own and is called `wasi_preview2`.
+    pub fn module_name(self) -> &'static str {
+        match self {
+            WasiSnapshot::Unstable => "wasi_unstable",
+            WasiSnapshot::Preview1 => "wasi_snapshot_preview1",
+            WasiSnapshot::Preview2 => "wasi_preview2",
+        }
+    }
+
+    /// The signature the snapshot defines for the given call, if the
+    /// snapshot has such a call
+    pub fn canonical_signature(self, name: &str) -> Option<Signature> {
+        match self {
+            // Added to wasi_snapshot_preview1 after wasi_unstable was frozen
+            WasiSnapshot::Unstable if name == "sock_accept" => return None,
+            // Interfaces are typed by the component model, not by core wasm
+            WasiSnapshot::Preview2 => return None,
+            _ => {}
+        }
+        CALLS
+            .iter()
+            .find(|(call, _, _)| *call == name)
+            .map(|(_, params, results)| Signature {
+                params: params.to_vec(),
+                results: results.to_vec(),
+            })
+    }
+
+    /// How the given call behaves differently in this snapshot compared to
+    /// `wasi_snapshot_preview1`, if at all.
+    pub fn abi_difference(self, name: &str) -> Option<AbiDifference> {
+        match self {
+            WasiSnapshot::Unstable => match name {
+                "fd_seek" => Some(AbiDifference::SeekWhence),
+                "fd_filestat_get" | "path_filestat_get" => Some(AbiDifference::FilestatLayout),
+                "poll_oneoff" => Some(AbiDifference::ClockSubscription),
+                _ => None,
+            },
+            WasiSnapshot::Preview1 | WasiSnapshot::Preview2 => None,
+        }
+    }
+}
+
+/// The category of a WASI 0.2 interface, such as `file_system` for
+/// `wasi:filesystem/types@0.2.0`, named like the categories of the calls of
+/// the older snapshots
+pub(crate) fn interface_category(interface: &str) -> Option<&'static str> {
+    let interface = interface.split('@').next().unwrap_or(interface);
+    let category = match interface {
+        "wasi:cli/environment" => "environment",
+        "wasi:cli/exit" => "process",
+        interface if interface.starts_with("wasi:cli/") => "file_system",
+        interface if interface.starts_with("wasi:filesystem/") => "file_system",
+        interface if interface.starts_with("wasi:io/") => "file_system",
+        interface if interface.starts_with("wasi:clocks/") => "environment",
+        interface if interface.starts_with("wasi:random/") => "environment",
+        interface if interface.starts_with("wasi:sockets/") => "network",
+        interface if interface.starts_with("wasi:http/") => "network",
+        _ => return None,
+    };
+    Some(category)
+}
+
+const I32: ValueType = ValueType::I32;
+const I64: ValueType = ValueType::I64;
+
+/// The core wasm signatures of the WASI calls. They are the same in both
+/// snapshots, the ABI differences are in how memory is laid out.
+#[rustfmt::skip]
+const CALLS: &[(&str, &[ValueType], &[ValueType])] = &[
+    ("args_get", &[I32, I32], &[I32]),
+    ("args_sizes_get", &[I32, I32], &[I32]),
+    ("environ_get", &[I32, I32], &[I32]),
+    ("environ_sizes_get", &[I32, I32], &[I32]),
+    ("clock_res_get", &[I32, I32], &[I32]),
+    ("clock_time_get", &[I32, I64, I32], &[I32]),
+    ("fd_advise", &[I32, I64, I64, I32], &[I32]),
+    ("fd_allocate", &[I32, I64, I64], &[I32]),
+    ("fd_close", &[I32], &[I32]),
+    ("fd_datasync", &[I32], &[I32]),
+    ("fd_fdstat_get", &[I32, I32], &[I32]),
+    ("fd_fdstat_set_flags", &[I32, I32], &[I32]),
+    ("fd_fdstat_set_rights", &[I32, I64, I64], &[I32]),
+    ("fd_filestat_get", &[I32, I32], &[I32]),
+    ("fd_filestat_set_size", &[I32, I64], &[I32]),
+    ("fd_filestat_set_times", &[I32, I64, I64, I32], &[I32]),
+    ("fd_pread", &[I32, I32, I32, I64, I32], &[I32]),
+    ("fd_prestat_get", &[I32, I32], &[I32]),
+    ("fd_prestat_dir_name", &[I32, I32, I32], &[I32]),
+    ("fd_pwrite", &[I32, I32, I32, I64, I32], &[I32]),
+    ("fd_read", &[I32, I32, I32, I32], &[I32]),
+    ("fd_readdir", &[I32, I32, I32, I64, I32], &[I32]),
+    ("fd_renumber", &[I32, I32], &[I32]),
+    ("fd_seek", &[I32, I64, I32, I32], &[I32]),
+    ("fd_sync", &[I32], &[I32]),
+    ("fd_tell", &[I32, I32], &[I32]),
+    ("fd_write", &[I32, I32, I32, I32], &[I32]),
+    ("path_create_directory", &[I32, I32, I32], &[I32]),
+    ("path_filestat_get", &[I32, I32, I32, I32, I32], &[I32]),
+    ("path_filestat_set_times", &[I32, I32, I32, I32, I64, I64, I32], &[I32]),
+    ("path_link", &[I32, I32, I32, I32, I32, I32, I32], &[I32]),
+    ("path_open", &[I32, I32, I32, I32, I32, I64, I64, I32, I32], &[I32]),
+    ("path_readlink", &[I32, I32, I32, I32, I32, I32], &[I32]),
+    ("path_remove_directory", &[I32, I32, I32], &[I32]),
+    ("path_rename", &[I32, I32, I32, I32, I32, I32], &[I32]),
+    ("path_symlink", &[I32, I32, I32, I32, I32], &[I32]),
+    ("path_unlink_file", &[I32, I32, I32], &[I32]),
+    ("poll_oneoff", &[I32, I32, I32, I32], &[I32]),
+    ("proc_exit", &[I32], &[]),
+    ("proc_raise", &[I32], &[I32]),
+    ("sched_yield", &[], &[I32]),
+    ("random_get", &[I32, I32], &[I32]),
+    ("sock_accept", &[I32, I32, I32], &[I32]),
+    ("sock_recv", &[I32, I32, I32, I32, I32, I32], &[I32]),
+    ("sock_send", &[I32, I32, I32, I32, I32], &[I32]),
+    ("sock_shutdown", &[I32, I32], &[I32]),
+];
+
+/// A WASI import that does not match the definition of the call in its
+/// snapshot. A runtime refuses to instantiate a binary with such an import.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignatureMismatch {
+    pub snapshot: WasiSnapshot,
+    pub import: Import,
+    /// The signature the snapshot defines for the call
+    pub expected: Signature,
+}
+
+impl fmt::Display for SignatureMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let import = &self.import;
+        write!(f, "{}::{} is imported as ", import.module, import.field)?;
+        match (&import.signature, import.kind) {
+            (Some(signature), _) => write!(f, "{}", signature)?,
+            (None, ImportKind::Function) => write!(f, "a function of unknown type")?,
+            (None, kind) => write!(f, "a {}", kind.name())?,
+        }
+        write!(
+            f,
+            " but {} defines it as {}",
+            self.snapshot.module_name(),
+            self.expected
+        )
+    }
+}
+
+/// A place where the ABI of an older snapshot differs from
+/// `wasi_snapshot_preview1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AbiDifference {
+    SeekWhence,
+    FilestatLayout,
+    ClockSubscription,
+}
+
+impl AbiDifference {
+    pub fn description(self) -> &'static str {
+        match self {
+            AbiDifference::SeekWhence => {
+                "fd_seek: whence values are CUR=0, END=1, SET=2 instead of SET=0, CUR=1, END=2"
+            }
+            AbiDifference::FilestatLayout => {
+                "filestat: nlink is a u32 instead of a u64, shifting the fields after it (56 bytes instead of 64)"
+            }
+            AbiDifference::ClockSubscription => {
+                "poll_oneoff: clock subscriptions carry an extra leading identifier field"
+            }
+        }
+    }
+}
+
+/// The WASI calls a binary imports, grouped by the system resource they
+/// give access to
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WasiAssumptions {
+    pub snapshots: Vec<WasiSnapshot>,
+    pub abi_differences: Vec<AbiDifference>,
+    /// Imports whose type differs from the call of the same name in their
+    /// snapshot
+    pub signature_mismatches: Vec<SignatureMismatch>,
+    pub file_system: Vec<Import>,
+    pub environment: Vec<Import>,
+    pub process: Vec<Import>,
+    pub network: Vec<Import>,
+    pub unknown: Vec<Import>,
+}
+
+impl WasiAssumptions {
+    pub(crate) fn new() -> WasiAssumptions {
+        WasiAssumptions {
+            snapshots: Vec::new(),
+            abi_differences: Vec::new(),
+            signature_mismatches: Vec::new(),
+            file_system: Vec::new(),
+            environment: Vec::new(),
+            process: Vec::new(),
+            network: Vec::new(),
+            unknown: Vec::new(),
+        }
+    }
+
+    pub(crate) fn add(&mut self, snapshot: WasiSnapshot, import: Import) {
+        if !self.snapshots.contains(&snapshot) {
+            self.snapshots.push(snapshot);
+        }
+        if let Some(difference) = snapshot.abi_difference(&import.field) {
+            if !self.abi_differences.contains(&difference) {
+                self.abi_differences.push(difference);
+            }
+        }
+        if let Some(expected) = snapshot.canonical_signature(&import.field) {
+            if import.kind != ImportKind::Function || import.signature.as_ref() != Some(&expected) {
+                self.signature_mismatches.push(SignatureMismatch {
+                    snapshot,
+                    import: import.clone(),
+                    expected,
+                });
+            }
+        }
+        if snapshot == WasiSnapshot::Preview2 {
+            match interface_category(&import.module) {
+                Some("file_system") => self.file_system.push(import),
+                Some("environment") => self.environment.push(import),
+                Some("process") => self.process.push(import),
+                Some("network") => self.network.push(import),
+                _ => self.unknown.push(import),
+            }
+            return;
+        }
+        match import.field.as_str() {
+            "args_get" | "args_sizes_get" | "clock_res_get" | "clock_time_get" | "random_get"
+            | "environ_get" | "environ_sizes_get" => self.environment.push(import),
+            "fd_advise"
+            | "fd_allocate"
+            | "fd_close"
+            | "fd_datasync"
+            | "fd_fdstat_get"
+            | "fd_fdstat_set_flags"
+            | "fd_fdstat_set_rights"
+            | "fd_filestat_get"
+            | "fd_filestat_set_size"
+            | "fd_filestat_set_times"
+            | "fd_pread"
+            | "fd_prestat_get"
+            | "fd_prestat_dir_name"
+            | "fd_pwrite"
+            | "fd_read"
+            | "fd_readdir"
+            | "fd_renumber"
+            | "fd_seek"
+            | "fd_sync"
+            | "fd_tell"
+            | "fd_write"
+            | "path_create_directory"
+            | "path_filestat_get"
+            | "path_filestat_set_times"
+            | "path_link"
+            | "path_open"
+            | "path_readlink"
+            | "path_remove_directory"
+            | "path_rename"
+            | "path_symlink"
+            | "path_unlink_file"
+            | "poll_oneoff" => self.file_system.push(import),
+            "proc_exit" | "proc_raise" | "sched_yield" => self.process.push(import),
+            "sock_accept" | "sock_recv" | "sock_send" | "sock_shutdown" => {
+                self.network.push(import)
+            }
+            _ => self.unknown.push(import),
+        }
+    }
+
+    /// The known categories of calls, named the way policies and the JSON
+    /// output refer to them
+    pub fn categories(&self) -> [(&'static str, &[Import]); 4] {
+        [
+            ("file_system", &self.file_system),
+            ("environment", &self.environment),
+            ("process", &self.process),
+            ("network", &self.network),
+        ]
+    }
+
+    pub fn count(&self) -> usize {
+        self.file_system.len()
+            + self.process.len()
+            + self.environment.len()
+            + self.network.len()
+            + self.unknown.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{fields, import};
+
+    #[test]
+    fn recognizes_snapshot_modules() {
+        assert_eq!(
+            WasiSnapshot::from_module_name("wasi_unstable"),
+            Some(WasiSnapshot::Unstable)
+        );
+        assert_eq!(
+            WasiSnapshot::from_module_name("wasi_snapshot_preview1"),
+            Some(WasiSnapshot::Preview1)
+        );
+        assert_eq!(WasiSnapshot::from_module_name("wasi"), None);
+        assert_eq!(WasiSnapshot::from_module_name("env"), None);
+    }
+
+    #[test]
+    fn only_wasi_unstable_differs_in_abi() {
+        for (call, difference) in [
+            ("fd_seek", AbiDifference::SeekWhence),
+            ("fd_filestat_get", AbiDifference::FilestatLayout),
+            ("path_filestat_get", AbiDifference::FilestatLayout),
+            ("poll_oneoff", AbiDifference::ClockSubscription),
+        ] {
+            assert_eq!(
+                WasiSnapshot::Unstable.abi_difference(call),
+                Some(difference)
+            );
+            assert_eq!(WasiSnapshot::Preview1.abi_difference(call), None);
+        }
+        assert_eq!(WasiSnapshot::Unstable.abi_difference("fd_write"), None);
+    }
+
+    #[test]
+    fn lists_each_abi_difference_once() {
+        let mut wasi = WasiAssumptions::new();
+        for call in [
+            "fd_filestat_get",
+            "path_filestat_get",
+            "fd_seek",
+            "fd_write",
+        ] {
+            wasi.add(WasiSnapshot::Unstable, import("wasi_unstable", call));
+        }
+        assert_eq!(wasi.snapshots, vec![WasiSnapshot::Unstable]);
+        assert_eq!(
+            wasi.abi_differences,
+            vec![AbiDifference::FilestatLayout, AbiDifference::SeekWhence]
+        );
+    }
+
+    #[test]
+    fn sorts_calls_by_resource() {
+        let mut wasi = WasiAssumptions::new();
+        for call in [
+            "fd_write",
+            "environ_get",
+            "proc_exit",
+            "sock_send",
+            "fd_frobnicate",
+        ] {
+            wasi.add(
+                WasiSnapshot::Preview1,
+                import("wasi_snapshot_preview1", call),
+            );
+        }
+        assert_eq!(fields(&wasi.file_system), vec!["fd_write"]);
+        assert_eq!(fields(&wasi.environment), vec!["environ_get"]);
+        assert_eq!(fields(&wasi.process), vec!["proc_exit"]);
+        assert_eq!(fields(&wasi.network), vec!["sock_send"]);
+        assert_eq!(fields(&wasi.unknown), vec!["fd_frobnicate"]);
+        assert_eq!(wasi.count(), 5);
+    }
+
+    #[test]
+    fn checks_signatures_against_the_snapshot() {
+        let mut wasi = WasiAssumptions::new();
+        let mut close = import("wasi_snapshot_preview1", "fd_close");
+        close.signature = Some(Signature {
+            params: vec![ValueType::I32],
+            results: vec![ValueType::I32],
+        });
+        let mut exit = import("wasi_snapshot_preview1", "proc_exit");
+        exit.signature = Some(Signature {
+            params: vec![ValueType::I64],
+            results: vec![],
+        });
+        let mut memory = import("wasi_snapshot_preview1", "random_get");
+        memory.kind = ImportKind::Memory;
+        for import in [
+            close,
+            exit,
+            memory,
+            import("wasi_snapshot_preview1", "fd_frob"),
+        ] {
+            wasi.add(WasiSnapshot::Preview1, import);
+        }
+        let mismatches: Vec<_> = wasi
+            .signature_mismatches
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        assert_eq!(
+            mismatches,
+            vec![
+                "wasi_snapshot_preview1::proc_exit is imported as (i64) -> () \
+                 but wasi_snapshot_preview1 defines it as (i32) -> ()",
+                "wasi_snapshot_preview1::random_get is imported as a memory \
+                 but wasi_snapshot_preview1 defines it as (i32, i32) -> i32",
+            ]
+        );
+    }
+
+    #[test]
+    fn sock_accept_is_not_in_wasi_unstable() {
+        assert!(WasiSnapshot::Preview1
+            .canonical_signature("sock_accept")
+            .is_some());
+        assert_eq!(
+            WasiSnapshot::Unstable.canonical_signature("sock_accept"),
+            None
+        );
+    }
+
+    #[test]
+    fn categorizes_preview2_interfaces() {
+        for (interface, category) in [
+            ("wasi:cli/environment@0.2.0", Some("environment")),
+            ("wasi:cli/exit", Some("process")),
+            ("wasi:cli/stdout@0.2.3", Some("file_system")),
+            ("wasi:filesystem/preopens@0.2.0", Some("file_system")),
+            ("wasi:io/streams@0.2.0", Some("file_system")),
+            ("wasi:clocks/wall-clock@0.2.0", Some("environment")),
+            ("wasi:random/random@0.2.0", Some("environment")),
+            ("wasi:sockets/udp@0.2.0", Some("network")),
+            ("wasi:http/outgoing-handler@0.2.0", Some("network")),
+            ("wasi:keyvalue/store@0.2.0", None),
+            ("my:app/logger", None),
+        ] {
+            assert_eq!(interface_category(interface), category, "{}", interface);
+        }
+        assert_eq!(
+            WasiSnapshot::from_module_name("wasi:io/poll@0.2.0"),
+            Some(WasiSnapshot::Preview2)
+        );
+        assert_eq!(WasiSnapshot::Preview2.canonical_signature("fd_write"), None);
+    }
+}
@@ -0,0 +1,448 @@
+use crate::{module::ParsedModule, profile::Classifier, Import, Profile};
+use wasmparser::{ExternalKind, Operator, RefType, TypeRef};
+
+/// The modules wasm-bindgen imports its JS glue from. Before the
+/// wasm-bindgen CLI runs, imports come from placeholder modules.
+const PLACEHOLDER: &str = "__wbindgen_placeholder__";
+const EXTERNREF_XFORM: &str = "__wbindgen_externref_xform__";
+
+/// The JS APIs a binary built with wasm-bindgen expects its glue code to
+/// provide, grouped by what they give access to
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WasmBindgenAssumptions {
+    /// Whether the imports still come from `__wbindgen_placeholder__`,
+    /// meaning the wasm-bindgen CLI has not processed the binary yet
+    pub unprocessed: bool,
+    /// Whether JS values are passed around as `externref` rather than as
+    /// indices into a heap kept by the glue code
+    pub externref: bool,
+    /// The JS types named by the `__wbindgen_describe_*` functions
+    pub described_types: Vec<String>,
+    pub dom: Vec<Import>,
+    pub fetch: Vec<Import>,
+    pub timers: Vec<Import>,
+    pub console: Vec<Import>,
+    pub crypto: Vec<Import>,
+    /// Any other JS function or property
+    pub js_interop: Vec<Import>,
+    /// The `__wbindgen_*` helpers of the glue code, such as creating
+    /// strings or throwing errors
+    pub intrinsics: Vec<Import>,
+    /// The JS types each import's describe function names
+    #[cfg_attr(feature = "serde", serde(skip))]
+    descriptions: Vec<(String, Vec<String>)>,
+}
+
+impl WasmBindgenAssumptions {
+    /// Recognize a binary built with wasm-bindgen by the modules it imports
+    /// from and by its `__wbindgen_*` exports
+    pub(crate) fn detect(module: &ParsedModule) -> Option<WasmBindgenAssumptions> {
+        let imports = module.imports.iter().any(|i| is_glue(i.module));
+        let exports = module
+            .exports
+            .iter()
+            .any(|e| e.name.starts_with("__wbindgen_"));
+        if !imports && !exports {
+            return None;
+        }
+
+        // The glue code either provides the table of JS values or fills the
+        // one the binary exports
+        let externref_table = |ty: &TypeRef| match ty {
+            TypeRef::Table(table) => table.element_type == RefType::EXTERNREF,
+            _ => false,
+        };
+        let externref =
+            module.imports.iter().any(|i| {
+                i.module == EXTERNREF_XFORM || (is_glue(i.module) && externref_table(&i.ty))
+            }) || module
+                .exports
+                .iter()
+                .any(|e| e.name == "__externref_table_alloc");
+
+        let mut descriptions = Vec::new();
+        let mut described_types = Vec::new();
+        for export in &module.exports {
+            let import = match export.name.strip_prefix("__wbindgen_describe_") {
+                Some(import) if export.kind == ExternalKind::Func => import,
+                _ => continue,
+            };
+            let types = describe(module, export.index);
+            for name in &types {
+                if !described_types.contains(name) {
+                    described_types.push(name.clone());
+                }
+            }
+            descriptions.push((import.to_owned(), types));
+        }
+
+        Some(WasmBindgenAssumptions {
+            unprocessed: module.imports.iter().any(|i| i.module == PLACEHOLDER),
+            externref,
+            described_types,
+            descriptions,
+            ..WasmBindgenAssumptions::default()
+        })
+    }
+
+    /// The group of JS API an import belongs to, judged by the name of the
+    /// JS function and the types its describe function names
+    fn group(&self, name: &str) -> Group {
+        if name.starts_with("__wbindgen_") {
+            return Group::Intrinsics;
+        }
+        let method = js_name(name);
+        if let Some(group) = method_group(method) {
+            return group;
+        }
+        if let Some(class) = method.strip_prefix("instanceof_") {
+            return class_group(class).unwrap_or(Group::JsInterop);
+        }
+        self.descriptions
+            .iter()
+            .filter(|(import, _)| import == name)
+            .flat_map(|(_, types)| types)
+            .find_map(|t| class_group(t))
+            .unwrap_or(Group::JsInterop)
+    }
+}
+
+impl Classifier for WasmBindgenAssumptions {
+    fn classify(&mut self, import: Import) -> Result<(), Import> {
+        if !is_glue(&import.module)
+            || !(import.field.starts_with("__wbg_") || import.field.starts_with("__wbindgen_"))
+        {
+            return Err(import);
+        }
+        let calls = match self.group(&import.field) {
+            Group::Dom => &mut self.dom,
+            Group::Fetch => &mut self.fetch,
+            Group::Timers => &mut self.timers,
+            Group::Console => &mut self.console,
+            Group::Crypto => &mut self.crypto,
+            Group::JsInterop => &mut self.js_interop,
+            Group::Intrinsics => &mut self.intrinsics,
+        };
+        calls.push(import);
+        Ok(())
+    }
+}
+
+impl Profile for WasmBindgenAssumptions {
+    fn id(&self) -> &'static str {
+        "wasm_bindgen"
+    }
+
+    fn name(&self) -> &'static str {
+        "wasm-bindgen"
+    }
+
+    fn categories(&self) -> Vec<(&'static str, &[Import])> {
+        vec![
+            ("dom", &self.dom),
+            ("fetch", &self.fetch),
+            ("timers", &self.timers),
+            ("console", &self.console),
+            ("crypto", &self.crypto),
+            ("js_interop", &self.js_interop),
+            ("intrinsics", &self.intrinsics),
+        ]
+    }
+
+    fn notes(&self) -> Vec<String> {
+        let mut notes = Vec::new();
+        if self.unprocessed {
+            notes.push(
+                "The binary has not been processed by the wasm-bindgen CLI, it cannot be loaded as is"
+                    .to_owned(),
+            );
+        }
+        if self.externref {
+            notes.push("JS values are passed to the binary as externref".to_owned());
+        }
+        if !self.described_types.is_empty() {
+            notes.push(format!(
+                "The describe functions name the JS types {}",
+                self.described_types.join(", ")
+            ));
+        }
+        notes
+    }
+}
+
+enum Group {
+    Dom,
+    Fetch,
+    Timers,
+    Console,
+    Crypto,
+    JsInterop,
+    Intrinsics,
+}
+
+/// Whether wasm-bindgen's glue code provides the imports of the module.
+/// The bundler target names the module after the generated JS file.
+fn is_glue(module: &str) -> bool {
+    module == "wbg"
+        || module == PLACEHOLDER
+        || module == EXTERNREF_XFORM
+        || module.ends_with("_bg.js")
+}
+
+/// The JS name in an import like `__wbg_setTimeout_7d8ce1d54d8c6d3a`, which
+/// ends in a hash of the import's signature
+fn js_name(field: &str) -> &str {
+    let name = field.strip_prefix("__wbg_").unwrap_or(field);
+    match name.rsplit_once('_') {
+        Some((name, hash)) if hash.len() >= 8 && hash.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            name
+        }
+        _ => name,
+    }
+}
+
+#[rustfmt::skip]
+const TIMERS: &[&str] = &[
+    "setTimeout", "clearTimeout", "setInterval", "clearInterval", "requestAnimationFrame",
+    "cancelAnimationFrame", "requestIdleCallback", "cancelIdleCallback", "queueMicrotask", "now",
+];
+
+#[rustfmt::skip]
+const CONSOLE: &[&str] = &[
+    "log", "info", "warn", "error", "debug", "trace", "table", "dir", "group", "groupCollapsed",
+    "groupEnd", "time", "timeEnd", "timeLog", "count", "assert",
+];
+
+#[rustfmt::skip]
+const CRYPTO: &[&str] = &[
+    "crypto", "msCrypto", "getRandomValues", "randomFillSync", "randomUUID", "subtle", "digest",
+    "encrypt", "decrypt", "sign", "verify", "generateKey", "importKey", "exportKey", "deriveKey",
+    "deriveBits", "wrapKey", "unwrapKey",
+];
+
+#[rustfmt::skip]
+const FETCH: &[&str] = &[
+    "fetch", "arrayBuffer", "statusText", "redirected", "newwithstr", "newwithstrandinit",
+    "newwithrequest", "newwithrequestandinit",
+];
+
+#[rustfmt::skip]
+const DOM: &[&str] = &[
+    "document", "window", "body", "head", "documentElement", "createElement", "createElementNS",
+    "createTextNode", "createDocumentFragment", "getElementById", "getElementsByClassName",
+    "getElementsByTagName", "querySelector", "querySelectorAll", "appendChild", "removeChild",
+    "insertBefore", "replaceChild", "cloneNode", "append", "prepend", "remove", "setAttribute",
+    "getAttribute", "removeAttribute", "hasAttribute", "classList", "className", "style",
+    "setProperty", "innerHTML", "outerHTML", "innerText", "textContent", "nodeValue",
+    "parentNode", "parentElement", "firstChild", "lastChild", "nextSibling", "children",
+    "childNodes", "addEventListener", "removeEventListener", "dispatchEvent", "preventDefault",
+    "stopPropagation", "focus", "blur", "click", "location", "history", "getBoundingClientRect",
+    "scrollIntoView", "attachShadow",
+];
+
+fn method_group(method: &str) -> Option<Group> {
+    // Setters of properties are named like `setinnerHTML`
+    let property = method.strip_prefix("set").unwrap_or(method);
+    let group = if TIMERS.contains(&method) {
+        Group::Timers
+    } else if CONSOLE.contains(&method) {
+        Group::Console
+    } else if CRYPTO.contains(&method) {
+        Group::Crypto
+    } else if FETCH.contains(&method) {
+        Group::Fetch
+    } else if DOM.contains(&method) || DOM.contains(&property) {
+        Group::Dom
+    } else {
+        return None;
+    };
+    Some(group)
+}
+
+/// The group of a JS type, as named by web-sys
+fn class_group(class: &str) -> Option<Group> {
+    let group = match class {
+        "Request" | "RequestInit" | "Response" | "ResponseInit" | "Headers" | "AbortController"
+        | "AbortSignal" | "FormData" | "ReadableStream" => Group::Fetch,
+        "Crypto" | "SubtleCrypto" | "CryptoKey" | "CryptoKeyPair" => Group::Crypto,
+        "Console" => Group::Console,
+        "Window"
+        | "Document"
+        | "Element"
+        | "Node"
+        | "NodeList"
+        | "Event"
+        | "EventTarget"
+        | "MouseEvent"
+        | "KeyboardEvent"
+        | "InputEvent"
+        | "PointerEvent"
+        | "TouchEvent"
+        | "CssStyleDeclaration"
+        | "DomTokenList"
+        | "DomRect"
+        | "Location"
+        | "History"
+        | "Text"
+        | "Comment"
+        | "DocumentFragment"
+        | "ShadowRoot"
+        | "HtmlCollection" => Group::Dom,
+        class if class.starts_with("Html") || class.starts_with("Svg") => Group::Dom,
+        _ => return None,
+    };
+    Some(group)
+}
+
+/// The JS type names a describe function passes to `__wbindgen_describe`.
+/// Named types are described by their length followed by one constant per
+/// character, so runs of constants that spell an identifier are taken as
+/// type names.
+fn describe(module: &ParsedModule, function: u32) -> Vec<String> {
+    let body = match function
+        .checked_sub(module.imported_functions)
+        .and_then(|index| module.bodies.get(index as usize))
+    {
+        Some(body) => body,
+        None => return Vec::new(),
+    };
+    let mut constants = Vec::new();
+    if let Ok(mut operators) = body.get_operators_reader() {
+        while let Ok(operator) = operators.read() {
+            if let Operator::I32Const { value } = operator {
+                constants.push(value as u32);
+            }
+        }
+    }
+
+    let mut names = Vec::new();
+    let mut i = 0;
+    while i < constants.len() {
+        let length = constants[i] as usize;
+        let characters = constants.get(i + 1..i + 1 + length).unwrap_or(&[]);
+        let name: Option<String> = characters
+            .iter()
+            .map(|&c| char::from_u32(c).filter(|c| c.is_ascii_alphanumeric() || *c == '_'))
+            .collect();
+        match name {
+            Some(name) if length >= 2 && name.starts_with(|c: char| c.is_ascii_uppercase()) => {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+                i += length + 1;
+            }
+            _ => i += 1,
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{analyze, fields};
+
+    #[test]
+    fn groups_js_apis() {
+        let report = analyze(
+            r#"(module
+                (import "wbg" "__wbg_setTimeout_7d8ce1d54d8c6d3a" (func))
+                (import "wbg" "__wbg_log_1d3ae0273d8f4f8a" (func))
+                (import "wbg" "__wbg_getRandomValues_3aa56aa6edec874c" (func))
+                (import "wbg" "__wbg_fetch_f8d735ba6fe1b719" (func))
+                (import "wbg" "__wbg_setinnerHTML_26d69b59e1af99c7" (func))
+                (import "wbg" "__wbg_instanceof_Response_fc4327dbfcdf5ced" (func))
+                (import "wbg" "__wbg_myHelper_0123456789abcdef" (func))
+                (import "wbg" "__wbindgen_throw" (func))
+                (import "wbg" "unrelated" (func)))"#,
+        );
+        let bindgen = report.assumptions.wasm_bindgen.as_ref().unwrap();
+        assert_eq!(
+            fields(&bindgen.timers),
+            vec!["__wbg_setTimeout_7d8ce1d54d8c6d3a"]
+        );
+        assert_eq!(fields(&bindgen.console), vec!["__wbg_log_1d3ae0273d8f4f8a"]);
+        assert_eq!(
+            fields(&bindgen.crypto),
+            vec!["__wbg_getRandomValues_3aa56aa6edec874c"]
+        );
+        assert_eq!(
+            fields(&bindgen.fetch),
+            vec![
+                "__wbg_fetch_f8d735ba6fe1b719",
+                "__wbg_instanceof_Response_fc4327dbfcdf5ced"
+            ]
+        );
+        assert_eq!(
+            fields(&bindgen.dom),
+            vec!["__wbg_setinnerHTML_26d69b59e1af99c7"]
+        );
+        assert_eq!(
+            fields(&bindgen.js_interop),
+            vec!["__wbg_myHelper_0123456789abcdef"]
+        );
+        assert_eq!(fields(&bindgen.intrinsics), vec!["__wbindgen_throw"]);
+        assert_eq!(fields(&report.assumptions.unknown), vec!["unrelated"]);
+        assert!(!bindgen.unprocessed);
+        assert!(!bindgen.externref);
+    }
+
+    #[test]
+    fn groups_imports_by_their_described_types() {
+        // The describe function of the import spells "Request"
+        let report = analyze(
+            r#"(module
+                (import "__wbindgen_placeholder__" "__wbindgen_describe" (func $describe (param i32)))
+                (import "__wbindgen_placeholder__" "__wbg_send_0123456789abcdef" (func))
+                (func (export "__wbindgen_describe___wbg_send_0123456789abcdef")
+                    i32.const 7 call $describe
+                    i32.const 82 call $describe
+                    i32.const 101 call $describe
+                    i32.const 113 call $describe
+                    i32.const 117 call $describe
+                    i32.const 101 call $describe
+                    i32.const 115 call $describe
+                    i32.const 116 call $describe))"#,
+        );
+        let bindgen = report.assumptions.wasm_bindgen.as_ref().unwrap();
+        assert_eq!(bindgen.described_types, vec!["Request"]);
+        assert_eq!(fields(&bindgen.fetch), vec!["__wbg_send_0123456789abcdef"]);
+        assert!(bindgen.unprocessed);
+        assert_eq!(
+            bindgen.notes(),
+            vec![
+                "The binary has not been processed by the wasm-bindgen CLI, it cannot be loaded as is",
+                "The describe functions name the JS types Request",
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_externref_tables() {
+        let report = analyze(
+            r#"(module
+                (import "wbg" "__wbindgen_externrefs" (table 128 externref)))"#,
+        );
+        assert!(report.assumptions.wasm_bindgen.unwrap().externref);
+    }
+
+    #[test]
+    fn plain_wasi_modules_are_not_wasm_bindgen() {
+        let report = analyze(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32)))
+                (import "env" "__wbg_log_1d3ae0273d8f4f8a" (func)))"#,
+        );
+        assert!(report.assumptions.wasm_bindgen.is_none());
+        assert_eq!(report.assumptions.unknown.len(), 1);
+    }
+
+    #[test]
+    fn strips_signature_hashes() {
+        assert_eq!(js_name("__wbg_setTimeout_7d8ce1d54d8c6d3a"), "setTimeout");
+        assert_eq!(js_name("__wbg_new_short"), "new_short");
+        assert_eq!(js_name("__wbindgen_throw"), "__wbindgen_throw");
+    }
+}
@@ -0,0 +1,73 @@
+//! The exit codes of the command line tool
+
+use std::{env, fs, process::Command};
+
+/// Run kontrolleur on `bytes` and return its exit code
+fn exit_code(name: &str, bytes: &[u8]) -> i32 {
+    exit_code_with(name, bytes, &[])
+}
+
+/// Run kontrolleur with `args` on `bytes` and return its exit code
+fn exit_code_with(name: &str, bytes: &[u8], args: &[&str]) -> i32 {
+    let path = env::temp_dir().join(format!("kontrolleur-{}-{}.wasm", std::process::id(), name));
+    fs::write(&path, bytes).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_kontrolleur"))
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    output.status.code().unwrap()
+}
+
+#[test]
+fn succeeds_for_valid_binaries() {
+    let bytes = wat::parse_str(
+        r#"(module (import "wasi_snapshot_preview1" "sched_yield" (func (result i32))))"#,
+    )
+    .unwrap();
+    assert_eq!(exit_code("valid", &bytes), 0);
+}
+
+#[test]
+fn fails_for_binaries_that_are_not_wasm() {
+    assert_eq!(exit_code("magic", b"not wasm"), 3);
+    assert_eq!(exit_code("version", b"\0asm\x02\0\0\0"), 3);
+}
+
+#[test]
+fn fails_for_malformed_binaries() {
+    assert_eq!(exit_code("malformed", b"\0asm\x01\0\0\0\x01\x05\x01"), 4);
+}
+
+#[test]
+fn fails_for_unsupported_proposals() {
+    // A SIMD instruction with an opcode no proposal defines yet
+    let bytes = b"\0asm\x01\0\0\0\
+        \x01\x04\x01\x60\0\0\
+        \x03\x02\x01\0\
+        \x0a\x08\x01\x06\0\xfd\xff\xff\x03\x0b";
+    assert_eq!(exit_code("simd", bytes), 5);
+}
+
+#[test]
+fn fails_for_wasi_signature_mismatches() {
+    let bytes = wat::parse_str(
+        r#"(module (import "wasi_snapshot_preview1" "fd_close" (func (param i64) (result i32))))"#,
+    )
+    .unwrap();
+    assert_eq!(exit_code("mismatch", &bytes), 8);
+    assert_eq!(exit_code_with("mismatch_strict", &bytes, &["--strict"]), 8);
+}
+
+#[test]
+fn fails_for_profile_violations_only_when_strict() {
+    let bytes = wat::parse_str(
+        r#"(module
+            (import "ic0" "msg_reply" (func))
+            (func (export "canister_upgrade")))"#,
+    )
+    .unwrap();
+    assert_eq!(exit_code("violation", &bytes), 0);
+    assert_eq!(exit_code_with("violation_strict", &bytes, &["--strict"]), 9);
+}